@@ -4,11 +4,12 @@ mod controller;
 use log::{trace, error};
 use chrono::offset::Local;
 use chrono::DateTime;
+use std::process::ExitCode;
 use std::result::Result;
 use std::thread;
 use std::time::SystemTime;
 
-pub fn run() -> Result<(), anyhow::Error> {
+pub fn run() -> Result<ExitCode, anyhow::Error> {
 
   let start_time = Into::<DateTime<Local>>::into(SystemTime::now()).format("%Y-%m-%d_%H-%M-%S").to_string();
   let logger = logger::Logger::new(format!("log/{}.log", start_time));
@@ -19,13 +20,14 @@ pub fn run() -> Result<(), anyhow::Error> {
 
   trace!("Init...OK");
 
-  if let Err(e) = controller_thread.join() {
+  let exit_code = controller_thread.join().unwrap_or_else(|e| {
     error!("Controller terminated with an error: {:?}", e.downcast_ref::<&str>());
-  }
+    ExitCode::FAILURE
+  });
 
   trace!("Shutdown...OK");
 
   logger.stop();
 
-  Ok(())
+  Ok(exit_code)
 }
\ No newline at end of file
@@ -1,62 +1,139 @@
+mod campaign;
+mod editor;
 mod error;
 mod input;
+mod menu;
 mod output;
 mod state;
 
+use std::fs;
+use std::io::{self, Write};
 use std::path::Path;
+use std::process::ExitCode;
 use std::str::FromStr;
 use std::sync::mpsc::SendError;
 use std::time;
-use std::{thread::{self, JoinHandle}, sync::mpsc::{SyncSender, Receiver, TryRecvError}};
+use std::{thread::{self, JoinHandle}, sync::mpsc::{self, Sender, SyncSender, Receiver, TryRecvError}};
 
 use crossterm::event::{KeyEvent, KeyCode, KeyModifiers, KeyEventKind, KeyEventState};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use crossterm::style::Color;
+use chrono::{offset::Local, DateTime};
+use campaign::{Campaign, Profile};
+use editor::Editor;
 use input::Input;
+use menu::Menu;
 use output::{Literal, Char, Output};
-use state::State;
+use state::{replay::Replay, State};
 use log::error;
 
 use crate::common;
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
+  #[command(subcommand)] command: Option<Command>,
   // Path to `.lvl` file
-  #[arg(short = 'l', long)] level: String,
+  #[arg(short = 'l', long)] level: Option<String>,
+  // Path to `.lvl` file to create or change in the level editor
+  #[arg(short = 'e', long, conflicts_with = "level")] edit: Option<String>,
+  // Path to a directory of `.lvl` files or a manifest listing `.lvl` files to play one after another
+  #[arg(short = 'c', long, conflicts_with_all = ["level", "edit"])] campaign: Option<String>,
+  // Path to a `.rpl` file recorded while playing, which is played back instead of playing its level
+  #[arg(long, conflicts_with_all = ["level", "edit", "campaign"])] replay: Option<String>,
+  // Speed of the replay, `2` plays twice as fast as recorded
+  #[arg(long, default_value = "1", requires = "replay")] replay_speed: f64,
+  // Path to the profile, which keeps the progress in campaigns
+  #[arg(long, default_value = campaign::PROFILE_PATH)] profile: String,
+  // Directory of `.lvl` files listed in the menu, which opens without `--level`, `--edit` or `--campaign`
+  #[arg(long, default_value = "levels")] levels: String,
+  // Start the level over instead of asking to resume its autosaved game
+  #[arg(long, requires = "level")] no_resume: bool,
   // undo size
-  #[arg(short = 'u', long, default_value = "250")] undo: usize
+  #[arg(short = 'u', long, default_value = "250")] undo: usize,
+  // Search for a solution instead of playing the level
+  #[arg(long, requires = "level")] solve: bool,
+  // Maximum number of boards explored while solving
+  #[arg(long, default_value_t = state::solver::DEFAULT_SEARCH_LIMIT)] solve_limit: usize
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+  // Check `.lvl` files for errors and exit with a non-zero code, if there are any
+  Check { #[arg(required = true)] levels: Vec<String> }
 }
 
 #[derive(PartialEq, Eq)]
-enum ExecutionState { Run, Restart, Error, Quit }
+enum ExecutionState { Run, Restart, NextLevel, Play(usize), Error, Quit }
 
 const FORWARDING_RATE_IN_MSECS: u64   = 1;
 const QUICK_SHUTDOWN_IN_SECS  : u64   = 5;
 const TOO_MANY_QUICK_SHUTDOWNS: usize = 5;
+const TIMELINE_MOVED_PARTS    : usize = 8;
+const SAVE_NAME_MAX_LENGTH    : usize = 32;
+
+/// Channels to the thread maintaining the board: Either the game state, the level editor or the level menu
+enum Board {
+  Game(SyncSender<state::ControlStatePayload>, Receiver<state::StateControlPayload>),
+  Editor(SyncSender<editor::ControlEditorPayload>, Receiver<editor::EditorControlPayload>),
+  Menu(SyncSender<menu::ControlMenuPayload>, Receiver<menu::MenuControlPayload>)
+}
 
 struct Controller {
   control_input_send : SyncSender<input::ControlInputPayload>,
   control_output_send: SyncSender<output::ControlOutputPayload>,
-  control_state_send : SyncSender<state::ControlStatePayload>,
   input_control_recv : Receiver<input::InputControlPayload>,
   output_control_recv: Receiver<output::OutputControlPayload>,
-  state_control_recv : Receiver<state::StateControlPayload>,
+  board              : Board,
+  campaign_progress  : Option<(usize,usize,bool)>,
+  solved_turns       : Option<i32>,
+  timeline_rows      : Option<(u16,Vec<i32>)>,
+  turn_counter_y     : u16,
+  save_name          : Option<String>,
+  replay_thread      : Option<(Sender<()>, JoinHandle<error::IOResult>)>,
   input_thread       : JoinHandle<error::IOResult>,
   state_thread       : JoinHandle<error::IOResult>,
   output_thread      : JoinHandle<error::IOResult>
 }
 
 impl Controller {
-  fn new(args: &Args) -> Result<Self,error::IOError> {
+  fn new(args: &Args, campaign: Option<&Campaign>, show_menu: bool, replay: Option<&Replay>, resume: bool) -> Result<Self,error::IOError> {
     let (    input , control_input_send , input_control_recv ) = Input::new();
-    let (    state , control_state_send , state_control_recv ) = State::new_with_args(args.level.clone(), args.undo)?;
+    let mut replay_thread = None;
+    let (board, state_thread) = if let Some(edit_path) = args.edit.clone() {
+      let (editor, control_editor_send, editor_control_recv) = Editor::new(edit_path)?;
+      (Board::Editor(control_editor_send, editor_control_recv), thread::spawn(move || editor.maintain().map_err(|err| { error!("Editor thread shutdown with error: {}", err); err })))
+    } else if let (Some(campaign), true) = (campaign, show_menu) {
+      let levels = campaign.levels().iter().map(|level_path| (level_path.clone(), campaign.best_turns(level_path))).collect();
+      let (menu, control_menu_send, menu_control_recv) = Menu::new(levels, campaign.progress().0-1);
+      (Board::Menu(control_menu_send, menu_control_recv), thread::spawn(move || menu.maintain().map_err(|err| { error!("Menu thread shutdown with error: {}", err); err })))
+    } else if let Some(replay) = replay {
+      let (mut state, control_state_send, state_control_recv) = State::new_with_args(replay.level_path.clone(), args.undo)?;
+      state.disable_replay();
+      state.disable_autosave();
+      // Feed the recorded payloads to the state instead of the input, until the replay thread is stopped
+      let (stop_send, stop_recv) = mpsc::channel();
+      let (replay, speed, replay_state_send) = (replay.clone(), args.replay_speed, control_state_send.clone());
+      replay_thread = Some((stop_send, thread::spawn(move || replay.play(speed, replay_state_send, stop_recv))));
+      (Board::Game(control_state_send, state_control_recv), thread::spawn(move || state.maintain().map_err(|err| { error!("State thread shutdown with error: {}",  err); err })))
+    } else {
+      let level_path = campaign.map_or(args.level.clone().unwrap_or_default(), Campaign::level_path);
+      let (mut state, control_state_send, state_control_recv) = State::new_with_args(level_path, args.undo)?;
+      if resume { state.resume_autosave(); }
+      (Board::Game(control_state_send, state_control_recv), thread::spawn(move || state.maintain().map_err(|err| { error!("State thread shutdown with error: {}",  err); err })))
+    };
     let (mut output, control_output_send, output_control_recv) = Output::new()?;
     Ok(Self {
-      control_input_send, control_output_send, control_state_send,
-      input_control_recv, output_control_recv, state_control_recv,
+      control_input_send, control_output_send,
+      input_control_recv, output_control_recv,
+      board, state_thread,
+      campaign_progress: campaign.map(|campaign| { let (level, levels) = campaign.progress(); (level, levels, campaign.has_next()) }),
+      solved_turns     : None,
+      timeline_rows    : None,
+      turn_counter_y   : 0,
+      save_name        : None,
+      replay_thread,
       input_thread : thread::spawn(move || input.capture().map_err( |err| { error!("Input thread shutdown with error: {}",  err); err })),
-      state_thread : thread::spawn(move || state.maintain().map_err(|err| { error!("State thread shutdown with error: {}",  err); err })),
       output_thread: thread::spawn(move || output.print().map_err(  |err| { error!("Output thread shutdown with error: {}", err); err }))
     })
   }
@@ -64,124 +141,394 @@ impl Controller {
   /// Map key event onto its respective payload and send it to output
   /// Quit on pressing `q`
   #[inline]
-  fn update_state_on_key_event(&self, key_event: KeyEvent) -> Result<ExecutionState, error::IOError> {
+  fn update_state_on_key_event(control_state_send: &SyncSender<state::ControlStatePayload>, key_event: KeyEvent) -> Result<ExecutionState, error::IOError> {
     match if let KeyEvent { code, modifiers: KeyModifiers::NONE, kind: KeyEventKind::Press, state: KeyEventState::NONE } = key_event { Some(code) } else { None } {
       Some(KeyCode::Char('q')) => { return Ok(ExecutionState::Quit);                                                                   },
       Some(KeyCode::Char('8'))    |
-      Some(KeyCode::Up)        => { self.control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::Up))?;       },
-      Some(KeyCode::Char('9')) => { self.control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::UpRight))?;  },
+      Some(KeyCode::Up)        => { control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::Up))?;       },
+      Some(KeyCode::Char('9')) => { control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::UpRight))?;  },
       Some(KeyCode::Char('6'))    |
-      Some(KeyCode::Right    ) => { self.control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::Right))?;    },
-      Some(KeyCode::Char('3')) => { self.control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::DownRight))?;},
+      Some(KeyCode::Right    ) => { control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::Right))?;    },
+      Some(KeyCode::Char('3')) => { control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::DownRight))?;},
       Some(KeyCode::Char('2'))    |
-      Some(KeyCode::Down     ) => { self.control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::Down))?;     },
-      Some(KeyCode::Char('1')) => { self.control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::DownLeft))?; },
+      Some(KeyCode::Down     ) => { control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::Down))?;     },
+      Some(KeyCode::Char('1')) => { control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::DownLeft))?; },
       Some(KeyCode::Char('4'))    |
-      Some(KeyCode::Left     ) => { self.control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::Left))?;     },
-      Some(KeyCode::Char('7')) => { self.control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::UpLeft))?;   },
+      Some(KeyCode::Left     ) => { control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::Left))?;     },
+      Some(KeyCode::Char('7')) => { control_state_send.send(state::ControlStatePayload::MoveCursor(state::Direction::UpLeft))?;   },
       Some(KeyCode::Char('5'))    |
       Some(KeyCode::Char(' '))    |
-      Some(KeyCode::Enter    ) => { self.control_state_send.send(state::ControlStatePayload::Select)?;                                 },
+      Some(KeyCode::Enter    ) => { control_state_send.send(state::ControlStatePayload::Select)?;                                 },
       Some(KeyCode::Char('n')) => { return Ok(ExecutionState::Restart);                                                                },
-      Some(KeyCode::Char('u')) => { self.control_state_send.send(state::ControlStatePayload::Undo)?;                                   },
-      Some(KeyCode::Char('r')) => { self.control_state_send.send(state::ControlStatePayload::Redo)?;                                   },
-      Some(KeyCode::Char('s')) => { self.control_state_send.send(state::ControlStatePayload::Save)?;                                   },
-      Some(KeyCode::Char('l')) => { self.control_state_send.send(state::ControlStatePayload::Load)?;                                   },
+      Some(KeyCode::Char('u')) => { control_state_send.send(state::ControlStatePayload::Undo)?;                                   },
+      Some(KeyCode::Char('r')) => { control_state_send.send(state::ControlStatePayload::Redo)?;                                   },
+      Some(KeyCode::Char('[')) => { control_state_send.send(state::ControlStatePayload::PreviousBranch)?;                         },
+      Some(KeyCode::Char(']')) => { control_state_send.send(state::ControlStatePayload::NextBranch)?;                             },
+      Some(KeyCode::Char('b')) => { control_state_send.send(state::ControlStatePayload::Branches)?;                               },
+      Some(KeyCode::Char('t')) => { control_state_send.send(state::ControlStatePayload::Timeline)?;                               },
+      Some(KeyCode::Home     ) => { control_state_send.send(state::ControlStatePayload::GotoTurn(0))?;                            },
+      Some(KeyCode::End      ) => { control_state_send.send(state::ControlStatePayload::GotoTurn(i32::MAX))?;                     },
+      Some(KeyCode::Char('l')) => { control_state_send.send(state::ControlStatePayload::Load)?;                                   },
+      Some(KeyCode::Char('x'))    |
+      Some(KeyCode::Delete   ) => { control_state_send.send(state::ControlStatePayload::DeleteSlot)?;                             },
+      Some(KeyCode::Char('e')) => { control_state_send.send(state::ControlStatePayload::Export)?;                                 },
+      Some(KeyCode::Char('h')) => { control_state_send.send(state::ControlStatePayload::Hint)?;                                   },
+      Some(KeyCode::Char('o')) => { control_state_send.send(state::ControlStatePayload::Rotate)?;                                 },
       _ => ()
     }
     Ok(ExecutionState::Run)
   }
 
-  ///
+  /// Map key event onto its respective payload and send it to the editor
+  /// Quit on pressing `q`
   #[inline]
-  fn connectors_to_literal(connectors: i32, kind: output::Kind) -> output::Literal {
-    match (connectors, kind) {
-      (_         ,output::Kind::Removed ) => { output::Literal::Empty },
-      (_         ,output::Kind::Volatile) => { output::Literal::Volatile },
-      (0         ,_                     ) => { output::Literal::Wall  },
-      (connectors,kind                  ) => { output::Literal::Object(connectors,kind) }
+  fn update_editor_on_key_event(control_editor_send: &SyncSender<editor::ControlEditorPayload>, key_event: KeyEvent) -> Result<ExecutionState, error::IOError> {
+    match if let KeyEvent { code, modifiers: KeyModifiers::NONE, kind: KeyEventKind::Press, state: KeyEventState::NONE } = key_event { Some(code) } else { None } {
+      Some(KeyCode::Char('q')) => { return Ok(ExecutionState::Quit);                                                                     },
+      Some(KeyCode::Char('8'))    |
+      Some(KeyCode::Up)        => { control_editor_send.send(editor::ControlEditorPayload::MoveCursor(state::Direction::Up))?;          },
+      Some(KeyCode::Char('9')) => { control_editor_send.send(editor::ControlEditorPayload::MoveCursor(state::Direction::UpRight))?;     },
+      Some(KeyCode::Char('6'))    |
+      Some(KeyCode::Right    ) => { control_editor_send.send(editor::ControlEditorPayload::MoveCursor(state::Direction::Right))?;       },
+      Some(KeyCode::Char('3')) => { control_editor_send.send(editor::ControlEditorPayload::MoveCursor(state::Direction::DownRight))?;   },
+      Some(KeyCode::Char('2'))    |
+      Some(KeyCode::Down     ) => { control_editor_send.send(editor::ControlEditorPayload::MoveCursor(state::Direction::Down))?;        },
+      Some(KeyCode::Char('1')) => { control_editor_send.send(editor::ControlEditorPayload::MoveCursor(state::Direction::DownLeft))?;    },
+      Some(KeyCode::Char('4'))    |
+      Some(KeyCode::Left     ) => { control_editor_send.send(editor::ControlEditorPayload::MoveCursor(state::Direction::Left))?;        },
+      Some(KeyCode::Char('7')) => { control_editor_send.send(editor::ControlEditorPayload::MoveCursor(state::Direction::UpLeft))?;      },
+      Some(KeyCode::Char('5'))    |
+      Some(KeyCode::Char(' '))    |
+      Some(KeyCode::Enter    ) => { control_editor_send.send(editor::ControlEditorPayload::Place)?;                                     },
+      Some(KeyCode::Char('x'))    |
+      Some(KeyCode::Backspace)    |
+      Some(KeyCode::Delete   ) => { control_editor_send.send(editor::ControlEditorPayload::Erase)?;                                     },
+      Some(KeyCode::Tab      ) => { control_editor_send.send(editor::ControlEditorPayload::NextPart)?;                                  },
+      Some(KeyCode::Char('v')) => { control_editor_send.send(editor::ControlEditorPayload::NextVariant)?;                               },
+      Some(KeyCode::Char('s')) => { control_editor_send.send(editor::ControlEditorPayload::Save)?;                                      },
+      _ => ()
     }
+    Ok(ExecutionState::Run)
   }
 
-  /// Determine the next exeuction state based on payloads sent by child processes
-  fn next_exec_state(&mut self) -> ExecutionState {
-    #[inline]
-    fn send_handler<T>(mut exec_state: ExecutionState, result: Result<(), SendError<T>>, error_msg: &str) -> ExecutionState {
-      match result { Ok(()) => (), Err(e) => { error!("{}: {}", error_msg, e); exec_state = ExecutionState::Error; } }; exec_state
+  /// Map key event onto its respective payload and send it to the menu
+  /// Quit on pressing `q`
+  #[inline]
+  fn update_menu_on_key_event(control_menu_send: &SyncSender<menu::ControlMenuPayload>, key_event: KeyEvent) -> Result<ExecutionState, error::IOError> {
+    match if let KeyEvent { code, modifiers: KeyModifiers::NONE, kind: KeyEventKind::Press, state: KeyEventState::NONE } = key_event { Some(code) } else { None } {
+      Some(KeyCode::Char('q')) => { return Ok(ExecutionState::Quit);                                                      },
+      Some(KeyCode::Char('8'))    |
+      Some(KeyCode::Up)        => { control_menu_send.send(menu::ControlMenuPayload::MoveCursor(state::Direction::Up))?;   },
+      Some(KeyCode::Char('2'))    |
+      Some(KeyCode::Down     ) => { control_menu_send.send(menu::ControlMenuPayload::MoveCursor(state::Direction::Down))?; },
+      Some(KeyCode::Char('5'))    |
+      Some(KeyCode::Char(' '))    |
+      Some(KeyCode::Enter    ) => { control_menu_send.send(menu::ControlMenuPayload::Select)?;                             },
+      _ => ()
     }
+    Ok(ExecutionState::Run)
+  }
 
-    let mut exec_state = ExecutionState::Run;
-
-    exec_state = match self.input_control_recv.try_recv() {
-      // Interpret input and forward it to all listening threads
-      Ok(input::InputControlPayload::Key(key_event)) => {
-        self.update_state_on_key_event(key_event)
-          .unwrap_or_else(|e| { error!("Error updating state on key event: {}", e); ExecutionState::Error })
-      },
-      Ok(input::InputControlPayload::Mouse(pos)) => {
-        send_handler(exec_state, self.control_state_send.send(state::ControlStatePayload::SetCursorPosition(pos)), "Error sending set cursor position event to state")
+  /// While the name of a save is typed, Enter saves, Escape cancels
+  fn update_save_name_on_key_event(&mut self, key_event: KeyEvent) -> Result<ExecutionState, error::IOError> {
+    let (Board::Game(control_state_send, _), Some(save_name)) = (&self.board, self.save_name.as_mut()) else { return Ok(ExecutionState::Run) };
+    match key_event {
+      KeyEvent { code: KeyCode::Char(c), modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT, kind: KeyEventKind::Press, .. }
+        if save_name.chars().count() < SAVE_NAME_MAX_LENGTH => { save_name.push(c); },
+      KeyEvent { code: KeyCode::Backspace, kind: KeyEventKind::Press, .. } => { save_name.pop(); },
+      KeyEvent { code: KeyCode::Enter, kind: KeyEventKind::Press, .. } => {
+        control_state_send.send(state::ControlStatePayload::Save(save_name.clone()))?;
+        self.save_name = None;
+        return Ok(ExecutionState::Run)
       },
-      Ok(input::InputControlPayload::Resize(size)) => {
-        send_handler(exec_state, self.control_state_send.send(state::ControlStatePayload::SetBoardSize(size)), "Error sending resize event to state")
-      },
-      // If the input disconnects, we take it as an error
-      Err(TryRecvError::Disconnected) => { ExecutionState::Error },
-      _                               => { exec_state }
-    };
+      KeyEvent { code: KeyCode::Esc, kind: KeyEventKind::Press, .. } => { self.save_name = None; },
+      _ => ()
+    }
+    self.print_save_name()?;
+    Ok(ExecutionState::Run)
+  }
+
+  /// Print the name of the save while it is typed below the turn counter, or blank it once it is cancelled
+  fn print_save_name(&self) -> error::IOResult {
+    let prompt = self.save_name.as_ref().map_or(String::new(), |save_name| format!("Save as: {}_ (Enter: Save, Esc: Cancel)", save_name));
+    self.control_output_send.send(output::ControlOutputPayload::PrintChars(vec![
+      Char::new(Literal::String(format!("{:<1$}", prompt, SAVE_NAME_MAX_LENGTH+40)), (1,self.turn_counter_y+1), Some(Color::Yellow))
+    ]))?;
+    Ok(())
+  }
+
+  /// While a replay is played, only quit on pressing `q` or restart the replay on pressing `n`
+  #[inline]
+  fn update_replay_on_key_event(key_event: KeyEvent) -> ExecutionState {
+    match if let KeyEvent { code, modifiers: KeyModifiers::NONE, kind: KeyEventKind::Press, state: KeyEventState::NONE } = key_event { Some(code) } else { None } {
+      Some(KeyCode::Char('q')) => ExecutionState::Quit,
+      Some(KeyCode::Char('n')) => ExecutionState::Restart,
+      _                        => ExecutionState::Run
+    }
+  }
+
+  /// Map the connectors and kind of an object onto the literal printed for it, a teleporter or gate by the pair or direction its `shape` stands for
+  #[inline]
+  fn connectors_to_literal(connectors: i32, kind: output::Kind, shape: i32) -> output::Literal {
+    match (connectors, kind) {
+      (_         ,output::Kind::Removed   ) => { output::Literal::Empty },
+      (_         ,output::Kind::Volatile  ) => { output::Literal::Volatile },
+      (_         ,output::Kind::Teleporter) => { output::Literal::Teleporter(-shape) },
+      (_         ,output::Kind::Gate      ) => { output::Literal::Gate(crate::engine::GATE_SHAPE - shape) },
+      (_         ,output::Kind::Ice       ) => { output::Literal::Ice },
+      (_         ,output::Kind::Lock      ) => { output::Literal::Lock },
+      (0         ,_                       ) => { output::Literal::Wall  },
+      (connectors,kind                    ) => { output::Literal::Object(connectors,kind) }
+    }
+  }
+
+  /// Map the completeness of a board onto the text and color printed after its turn count
+  #[inline]
+  fn completeness_to_text(completeness: state::Completeness) -> (&'static str, Color) {
+    match completeness {
+      state::Completeness::Complete          => (if cfg!(windows) {"  OK "} else {" ✓  "} , Color::Green),
+      state::Completeness::PartiallyComplete => (if cfg!(windows) {" (OK)"} else {" (✓)"} , Color::Yellow),
+      state::Completeness::Incomplete        => ("     "                                  , Color::DarkGrey),
+      state::Completeness::Unsolvable        => (if cfg!(windows) {"  X  "} else {" ✗  "} , Color::Red)
+    }
+  }
 
-    exec_state = match self.state_control_recv.try_recv() {
+  #[inline]
+  fn send_handler<T>(mut exec_state: ExecutionState, result: Result<(), SendError<T>>, error_msg: &str) -> ExecutionState {
+    match result { Ok(()) => (), Err(e) => { error!("{}: {}", error_msg, e); exec_state = ExecutionState::Error; } }; exec_state
+  }
+
+  /// Forward payloads sent by the game state to the output
+  fn forward_state_payload(&mut self, exec_state: ExecutionState, payload: Result<state::StateControlPayload, TryRecvError>) -> ExecutionState {
+    match payload {
       Ok(state::StateControlPayload::ClearTerminal) => {
-        send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::ClearTerminal), "Error clearing terminal")
+        self.timeline_rows = None;
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::ClearTerminal), "Error clearing terminal")
       },
       Ok(state::StateControlPayload::PrintObjects(objects)) => {
-        send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::PrintChars(
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::PrintChars(
           objects.into_iter()
             .map(|obj| Char::new(
-              Controller::connectors_to_literal(obj.connectors(),output::Kind::from_str(obj.kind().as_str()).unwrap_or(output::Kind::None)),
+              Controller::connectors_to_literal(obj.connectors(),output::Kind::from_str(obj.kind().as_str()).unwrap_or(output::Kind::None),obj.shape()),
               obj.pos(),
               obj.color()))
             .collect()
         )), "Error printing objects to output")
       },
       Ok(state::StateControlPayload::SetCursorPosition((x,y))) => {
-        send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::SetCursorPosition((x,y))), "Error sending cursor position to output")
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::SetCursorPosition((x,y))), "Error sending cursor position to output")
       },
       Ok(state::StateControlPayload::MoveShape(here_shape,there_shape)) => {
-        send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::PrintChars({
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::PrintChars({
           here_shape.into_iter()
             .map(|obj| Char::new(Literal::Empty, obj.pos(), None))
             .chain(there_shape.into_iter()
                      .map(|obj| Char::new(
-                      Controller::connectors_to_literal(obj.connectors(), output::Kind::from_str(obj.kind().as_str()).unwrap_or(output::Kind::None)),
+                      Controller::connectors_to_literal(obj.connectors(), output::Kind::from_str(obj.kind().as_str()).unwrap_or(output::Kind::None), obj.shape()),
                       obj.pos(),
                       obj.color())))
             .collect()
         })), "Error sending shapes that move from here to there to output")
       },
       Ok(state::StateControlPayload::ResizeTerminal(size)) => {
-        send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::ResizeTerminal(size)), "Error resizing terminal")
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::ResizeTerminal(size)), "Error resizing terminal")
+      },
+      Ok(state::StateControlPayload::TurnCounter(y_pos, turn, complete, header)) => {
+        let (complete_text, complete_color) = Controller::completeness_to_text(complete);
+        self.turn_counter_y = y_pos;
+        // Campaign progress, title and par of the level go in front of the turn counter, which changes in width
+        let campaign_text = self.campaign_progress.map_or(String::new(), |(level, levels, _)| format!("Level {}/{} | ", level, levels));
+        let level_text = match (header.title, header.par) {
+          (Some(title), Some(par)) => format!("{}{} (Par: {}) | ", campaign_text, title, par),
+          (Some(title), None     ) => format!("{}{} | ", campaign_text, title),
+          (None       , Some(par)) => format!("{}Par: {} | ", campaign_text, par),
+          (None       , None     ) => campaign_text
+        };
+        let mut chars = vec![
+          Char::new(Literal::String(level_text.clone()), (1,y_pos), Some(Color::DarkGrey)),
+          Char::new(Literal::String(format!("Turn: {}{}", turn, complete_text)), (1+level_text.chars().count() as u16,y_pos), Some(complete_color))
+        ];
+        // A level is solved, once it is complete or as complete as its header requires
+        if complete == state::Completeness::Complete || (complete == state::Completeness::PartiallyComplete && header.completeness == Some(state::Completeness::PartiallyComplete)) {
+          self.solved_turns = Some(self.solved_turns.map_or(turn, |solved_turns| solved_turns.min(turn)));
+          if let Some((_, _, has_next)) = self.campaign_progress {
+            chars.push(Char::new(Literal::String((if has_next { "Solved! Tab: Next level" } else { "Solved! Campaign complete" }).to_string()), (1,y_pos+1), Some(Color::Green)));
+          }
+        }
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::PrintChars(chars)), "Error printing `level complete`")
+      },
+      Ok(state::StateControlPayload::Branches(y_pos, branches)) => {
+        // List the branches of the history below the turn counter, marking the current one
+        let mut chars = vec![Char::new(Literal::String("Branches ([/]: Switch, b: Hide):".to_string()), (1,y_pos), Some(Color::DarkGrey))];
+        for (index, branch) in branches.iter().enumerate() {
+          let (complete_text, complete_color) = branch.completeness.map_or(("", Color::DarkGrey), Controller::completeness_to_text);
+          chars.push(Char::new(
+            Literal::String(format!("{} {:>2}. Turn: {}{}", if branch.current { ">" } else { " " }, index+1, branch.turn, complete_text)),
+            (1,y_pos+1+index as u16),
+            Some(if branch.current { Color::White } else { complete_color })));
+        }
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::PrintChars(chars)), "Error printing branches")
+      },
+      Ok(state::StateControlPayload::Slots(y_pos, slots, selected)) => {
+        // List the saved games below the turn counter, the latest first, marking the selected one
+        let mut chars = vec![Char::new(Literal::String("Saves (Up/Down: Select, Enter: Load, x: Delete, l: Hide):".to_string()), (1,y_pos), Some(Color::DarkGrey))];
+        if slots.is_empty() {
+          chars.push(Char::new(Literal::String("  No saves yet, press s to save".to_string()), (1,y_pos+1), Some(Color::DarkGrey)));
+        }
+        for (index, slot) in slots.iter().enumerate() {
+          let (complete_text, complete_color) = Controller::completeness_to_text(slot.completeness);
+          chars.push(Char::new(
+            Literal::String(format!("{} {} | {} | Turn: {}{}", if index == selected { ">" } else { " " }, slot.saved, slot.name, slot.turn, complete_text)),
+            (1,y_pos+1+index as u16),
+            Some(if index == selected { Color::White } else { complete_color })));
+        }
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::PrintChars(chars)), "Error printing saves")
+      },
+      Ok(state::StateControlPayload::Message(message, color)) => {
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::PrintChars(vec![
+          Char::new(Literal::String(message), (1,self.turn_counter_y+1), Some(color))
+        ])), "Error printing message")
+      },
+      Ok(state::StateControlPayload::Timeline(y_pos, turns)) => {
+        // List the turns of the current branch below the turn counter with the parts moved in each turn, marking the current one
+        let mut chars = vec![Char::new(Literal::String("Timeline (Click/Home/End: Go to turn, t: Hide):".to_string()), (1,y_pos), Some(Color::DarkGrey))];
+        for (row, turn) in turns.iter().enumerate() {
+          let (complete_text, complete_color) = turn.completeness.map_or(("", Color::DarkGrey), Controller::completeness_to_text);
+          let moved: String = turn.moved.iter().take(TIMELINE_MOVED_PARTS)
+            .map(|obj| Controller::connectors_to_literal(obj.connectors(), output::Kind::from_str(obj.kind().as_str()).unwrap_or(output::Kind::None), obj.shape()).to_string())
+            .chain((turn.moved.len() > TIMELINE_MOVED_PARTS).then(|| "…".to_string()))
+            .collect();
+          let change = match (turn.direction, turn.removed) {
+            (None           , 0      ) => if turn.turn == 0 { "Start".to_string() } else { String::new() },
+            (None           , removed) => format!("{} removed", removed),
+            (Some(direction), 0      ) => format!("{} {:?}", moved, direction),
+            (Some(direction), removed) => format!("{} {:?}, {} removed", moved, direction, removed)
+          };
+          chars.push(Char::new(
+            Literal::String(format!("{} {:>4}: {}{}", if turn.current { ">" } else { " " }, turn.turn, change, complete_text)),
+            (1,y_pos+1+row as u16),
+            Some(if turn.current { Color::White } else { complete_color })));
+        }
+        self.timeline_rows = Some((y_pos+1, turns.iter().map(|turn| turn.turn).collect()));
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::PrintChars(chars)), "Error printing timeline")
+      },
+      Err(TryRecvError::Disconnected) => { ExecutionState::Error },
+      _                               => { exec_state }
+    }
+  }
+
+  /// Forward payloads sent by the editor to the output
+  fn forward_editor_payload(&self, exec_state: ExecutionState, payload: Result<editor::EditorControlPayload, TryRecvError>) -> ExecutionState {
+    match payload {
+      Ok(editor::EditorControlPayload::ClearTerminal) => {
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::ClearTerminal), "Error clearing terminal")
+      },
+      Ok(editor::EditorControlPayload::PrintChars(chars)) => {
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::PrintChars(chars)), "Error printing editor chars to output")
+      },
+      Ok(editor::EditorControlPayload::SetCursorPosition((x,y))) => {
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::SetCursorPosition((x,y))), "Error sending cursor position to output")
+      },
+      Err(TryRecvError::Disconnected) => { ExecutionState::Error },
+      _                               => { exec_state }
+    }
+  }
+
+  /// Forward payloads sent by the menu to the output, play the level once it is chosen
+  fn forward_menu_payload(&self, exec_state: ExecutionState, payload: Result<menu::MenuControlPayload, TryRecvError>) -> ExecutionState {
+    match payload {
+      Ok(menu::MenuControlPayload::ClearTerminal) => {
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::ClearTerminal), "Error clearing terminal")
+      },
+      Ok(menu::MenuControlPayload::PrintChars(chars)) => {
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::PrintChars(chars)), "Error printing menu chars to output")
       },
-      Ok(state::StateControlPayload::TurnCounter(y_pos, turn, complete)) => {
-        send_handler(exec_state, self.control_output_send.send(
-          output::ControlOutputPayload::PrintChars(vec![{
-                let (complete_text, complete_color) = match complete {
-                  state::Completeness::Complete          => (if cfg!(windows) {"  OK "} else {" ✓  "} , Color::Green),
-                  state::Completeness::PartiallyComplete => (if cfg!(windows) {" (OK)"} else {" (✓)"} , Color::Yellow),
-                  state::Completeness::Incomplete        => ("     "                                  , Color::DarkGrey)
-                };
-                Char::new(Literal::String(format!("Turn: {}{}", turn, complete_text)), (1,y_pos), Some(complete_color))
-              }
-            ]
-          )
-        ), "Error printing `level complete`")
+      Ok(menu::MenuControlPayload::SetCursorPosition((x,y))) => {
+        Controller::send_handler(exec_state, self.control_output_send.send(output::ControlOutputPayload::SetCursorPosition((x,y))), "Error sending cursor position to output")
       },
+      Ok(menu::MenuControlPayload::Play(level)) => { ExecutionState::Play(level) },
+      Err(TryRecvError::Disconnected) => { ExecutionState::Error },
+      _                               => { exec_state }
+    }
+  }
+
+  /// Turn listed in row `y` of the timeline, if it is shown
+  #[inline]
+  fn timeline_turn(&self, y: u16) -> Option<i32> {
+    self.timeline_rows.as_ref().and_then(|(first_y, turns)| y.checked_sub(*first_y).and_then(|row| turns.get(usize::from(row)).copied()))
+  }
+
+  /// Determine the next exeuction state based on payloads sent by child processes
+  fn next_exec_state(&mut self) -> ExecutionState {
+    let mut exec_state = ExecutionState::Run;
+
+    exec_state = match self.input_control_recv.try_recv() {
+      // Interpret input and forward it to all listening threads
+      // Advance to the next level of the campaign, once the current level is solved
+      Ok(input::InputControlPayload::Key(KeyEvent { code: KeyCode::Tab, modifiers: KeyModifiers::NONE, kind: KeyEventKind::Press, .. }))
+        if self.solved_turns.is_some() && self.campaign_progress.is_some_and(|(_, _, has_next)| has_next) => {
+        ExecutionState::NextLevel
+      },
+      Ok(input::InputControlPayload::Key(key_event)) if self.replay_thread.is_some() => {
+        Controller::update_replay_on_key_event(key_event)
+      },
+      Ok(input::InputControlPayload::Key(key_event)) if self.save_name.is_some() => {
+        self.update_save_name_on_key_event(key_event).unwrap_or_else(|e| { error!("Error updating the name of the save on key event: {}", e); ExecutionState::Error })
+      },
+      // Start typing the name of a save
+      Ok(input::InputControlPayload::Key(KeyEvent { code: KeyCode::Char('s'), modifiers: KeyModifiers::NONE, kind: KeyEventKind::Press, .. }))
+        if matches!(self.board, Board::Game(..)) => {
+        self.save_name = Some(String::new());
+        self.print_save_name().map_or_else(|e| { error!("Error printing the name of the save: {}", e); ExecutionState::Error }, |_| exec_state)
+      },
+      Ok(input::InputControlPayload::Key(key_event)) => {
+        match &self.board {
+          Board::Game(control_state_send, _)    => Controller::update_state_on_key_event(control_state_send, key_event),
+          Board::Editor(control_editor_send, _) => Controller::update_editor_on_key_event(control_editor_send, key_event),
+          Board::Menu(control_menu_send, _)     => Controller::update_menu_on_key_event(control_menu_send, key_event)
+        }.unwrap_or_else(|e| { error!("Error updating state on key event: {}", e); ExecutionState::Error })
+      },
+      Ok(input::InputControlPayload::Mouse(_)) if self.replay_thread.is_some() => { exec_state },
+      // Go to the turn clicked in the timeline
+      Ok(input::InputControlPayload::Mouse((_,y))) if self.timeline_turn(y).is_some() => {
+        match (&self.board, self.timeline_turn(y)) {
+          (Board::Game(control_state_send, _), Some(turn)) => Controller::send_handler(exec_state, control_state_send.send(state::ControlStatePayload::GotoTurn(turn)), "Error sending go to turn event to state"),
+          _                                                => exec_state
+        }
+      },
+      Ok(input::InputControlPayload::Mouse(pos)) => {
+        match &self.board {
+          Board::Game(control_state_send, _)    => Controller::send_handler(exec_state, control_state_send.send(state::ControlStatePayload::SetCursorPosition(pos)), "Error sending set cursor position event to state"),
+          Board::Editor(control_editor_send, _) => Controller::send_handler(exec_state, control_editor_send.send(editor::ControlEditorPayload::SetCursorPosition(pos)), "Error sending set cursor position event to editor"),
+          Board::Menu(control_menu_send, _)     => Controller::send_handler(exec_state, control_menu_send.send(menu::ControlMenuPayload::SetCursorPosition(pos)), "Error sending set cursor position event to menu")
+        }
+      },
+      Ok(input::InputControlPayload::Resize(size)) => {
+        match &self.board {
+          Board::Game(control_state_send, _)    => Controller::send_handler(exec_state, control_state_send.send(state::ControlStatePayload::SetBoardSize(size)), "Error sending resize event to state"),
+          Board::Editor(control_editor_send, _) => Controller::send_handler(exec_state, control_editor_send.send(editor::ControlEditorPayload::SetBoardSize(size)), "Error sending resize event to editor"),
+          Board::Menu(control_menu_send, _)     => Controller::send_handler(exec_state, control_menu_send.send(menu::ControlMenuPayload::SetBoardSize(size)), "Error sending resize event to menu")
+        }
+      },
+      // If the input disconnects, we take it as an error
       Err(TryRecvError::Disconnected) => { ExecutionState::Error },
       _                               => { exec_state }
     };
 
+    exec_state = match &self.board {
+      Board::Game(_, state_control_recv)    => { let payload = state_control_recv.try_recv(); self.forward_state_payload(exec_state, payload) },
+      Board::Editor(_, editor_control_recv) => self.forward_editor_payload(exec_state, editor_control_recv.try_recv()),
+      Board::Menu(_, menu_control_recv)     => self.forward_menu_payload(exec_state, menu_control_recv.try_recv())
+    };
+
     exec_state = match self.output_control_recv.try_recv() {
       Ok(output::OutputControlPayload::ReportTerminalSize(terminal_size)) => {
-        send_handler(exec_state, self.control_state_send.send(state::ControlStatePayload::SetBoardSize(terminal_size)), format!("Error reporting the terminal size ({:?}) from output to state", terminal_size).as_str())
+        let error_msg = format!("Error reporting the terminal size ({:?}) from output to state", terminal_size);
+        match &self.board {
+          Board::Game(control_state_send, _)    => Controller::send_handler(exec_state, control_state_send.send(state::ControlStatePayload::SetBoardSize(terminal_size)), error_msg.as_str()),
+          Board::Editor(control_editor_send, _) => Controller::send_handler(exec_state, control_editor_send.send(editor::ControlEditorPayload::SetBoardSize(terminal_size)), error_msg.as_str()),
+          Board::Menu(control_menu_send, _)     => Controller::send_handler(exec_state, control_menu_send.send(menu::ControlMenuPayload::SetBoardSize(terminal_size)), error_msg.as_str())
+        }
       },
       Err(TryRecvError::Disconnected) => { ExecutionState::Error },
       _                               => { exec_state }
@@ -219,63 +566,198 @@ impl Controller {
     }
     // Clean shutdown of input thread, if possible
     shutdown_thread(self.input_thread, self.control_input_send.send(input::ControlInputPayload::Shutdown));
-    // Clean shutdown of state thread, if possible
-    shutdown_thread(self.state_thread, self.control_state_send.send(state::ControlStatePayload::Shutdown));
+    // Stop the replay, before the state shuts down
+    let replay_thread = self.replay_thread.map(|(stop_send, replay_thread)| { drop(stop_send); replay_thread });
+    // Clean shutdown of state, editor or menu thread, if possible
+    match self.board {
+      Board::Game(control_state_send, _)    => shutdown_thread(self.state_thread, control_state_send.send(state::ControlStatePayload::Shutdown)),
+      Board::Editor(control_editor_send, _) => shutdown_thread(self.state_thread, control_editor_send.send(editor::ControlEditorPayload::Shutdown)),
+      Board::Menu(control_menu_send, _)     => shutdown_thread(self.state_thread, control_menu_send.send(menu::ControlMenuPayload::Shutdown))
+    }
+    // A replay thread waiting for the state to receive fails, once the state is shut down
+    if let Some(Err(e)) = replay_thread.map(JoinHandle::join) { error!("Error on replay thread join: {:?}", e.downcast_ref::<&str>()) }
     // Clean shutdown of output thread, if possible
     shutdown_thread(self.output_thread, self.control_output_send.send(output::ControlOutputPayload::Shutdown));
   }
 }
 
-pub fn run() {
+/// Search for a solution of the level and print its moves, returns true, if a solution was found
+fn solve(level_path: String, args: &Args) -> bool {
+  match State::new_with_args(level_path.clone(), args.undo).and_then(|(state,_,_)| state.solve(args.solve_limit)) {
+    Ok(search) => {
+      search.report(level_path.as_str()).iter().for_each(|line| println!("{line}"));
+      search.solution.is_some()
+    },
+    Err(e) => {
+      let error_message = format!("Could not solve level `{}`: {}", level_path, e);
+      error!("{error_message}");
+      println!("{error_message}");
+      false
+    }
+  }
+}
+
+/// Check every level and print its diagnostics, returns true, if no level has errors
+fn check(level_paths: &[String]) -> bool {
+  let mut error_count = 0;
+  for level_path in level_paths {
+    match fs::read_to_string(level_path).map_err(error::IOError::from).and_then(|level| State::new()?.0.check(level.as_str())) {
+      Ok(diagnostics) => {
+        for diagnostic in &diagnostics {
+          if diagnostic.pos.is_some() { println!("{}:{}", level_path, diagnostic) } else { println!("{}: {}", level_path, diagnostic) }
+        }
+        error_count += diagnostics.iter().filter(|d| d.severity == state::check::Severity::Error).count();
+      },
+      Err(e) => {
+        println!("{}: error: Could not check level: {}", level_path, e);
+        error_count += 1;
+      }
+    }
+  }
+  println!("Checked {} levels: {} errors", level_paths.len(), error_count);
+  error_count == 0
+}
+
+/// Ask on the terminal, before it enters raw mode, whether to resume the autosaved game of the level, if there is one
+fn ask_resume(level_path: &str) -> bool {
+  // Without DuckDB nothing is autosaved
+  if cfg!(not(feature = "duckdb")) { return false }
+  let Ok(autosaved) = fs::metadata(State::get_autosave_file_path(level_path)).and_then(|metadata| metadata.modified()) else { return false };
+  print!("Resume the game of `{}` autosaved at {}? [Y/n] ", level_path, Into::<DateTime<Local>>::into(autosaved).format("%Y-%m-%d %H:%M:%S"));
+  let mut answer = String::new();
+  io::stdout().flush().and_then(|_| io::stdin().read_line(&mut answer)).is_ok_and(|_| !answer.trim().to_lowercase().starts_with('n'))
+}
+
+pub fn run() -> ExitCode {
   let args = Args::parse();
-  if !Path::new(args.level.as_str()).exists() {
-    let error_message = format!("Level `{}` does not exist", args.level);
-    error!("{error_message}");
-    println!("{error_message}");
-    return;
+  if let Some(Command::Check { levels }) = &args.command {
+    return if check(levels) { ExitCode::SUCCESS } else { ExitCode::FAILURE };
+  }
+  let replay = match args.replay.as_ref().map(|replay_path| (replay_path, Replay::load(replay_path))) {
+    Some((_, Ok(replay))) if args.replay_speed > 0.0 => Some(replay),
+    Some((replay_path, result)) => {
+      let error_message = match result {
+        Ok(_)  => format!("Replay speed {} is not positive", args.replay_speed),
+        Err(e) => format!("Could not load replay `{}`: {}", replay_path, e)
+      };
+      error!("{error_message}");
+      println!("{error_message}");
+      return ExitCode::FAILURE;
+    },
+    None => None
+  };
+  // Without a level, level editor, campaign or replay, choose a level in the menu
+  let use_menu      = args.level.is_none() && args.edit.is_none() && args.campaign.is_none() && replay.is_none();
+  let mut show_menu = use_menu;
+  let mut campaign  = match args.campaign.as_ref().or(if use_menu { Some(&args.levels) } else { None }) {
+    Some(campaign_path) => match Profile::new(args.profile.clone()).and_then(|profile| Campaign::new(campaign_path, profile)) {
+      Ok(campaign) => Some(campaign),
+      Err(e)       => {
+        let error_message = format!("Could not load campaign `{}`: {}", campaign_path, e);
+        error!("{error_message}");
+        println!("{error_message}");
+        return ExitCode::FAILURE;
+      }
+    },
+    None                => None
+  };
+  if let (Some(level_path), true) = (args.level.clone(), args.solve) {
+    if !Path::new(level_path.as_str()).exists() {
+      let error_message = format!("Level `{}` does not exist", level_path);
+      error!("{error_message}");
+      println!("{error_message}");
+      return ExitCode::FAILURE;
+    }
+    return if solve(level_path, &args) { ExitCode::SUCCESS } else { ExitCode::FAILURE };
+  }
+  // Report errors in the levels before the terminal enters raw mode, the menu reports them once a level is selected
+  let checked_levels = match (campaign.as_ref(), use_menu) {
+    (Some(campaign), false) => campaign.levels().to_vec(),
+    _                       => args.level.iter().chain(replay.as_ref().map(|replay| &replay.level_path)).cloned().collect()
+  };
+  for level_path in checked_levels {
+    if !Path::new(level_path.as_str()).exists() {
+      let error_message = format!("Level `{}` does not exist", level_path);
+      error!("{error_message}");
+      println!("{error_message}");
+      return ExitCode::FAILURE;
+    }
+    if let Err(e) = State::parse(level_path.as_str()) {
+      let error_message = format!("Could not load level `{}`: {}", level_path, e);
+      error!("{error_message}");
+      println!("{error_message}");
+      return ExitCode::FAILURE;
+    }
   }
-  let mut controller = match Controller::new(&args) {
+  let mut resume     = args.level.as_ref().is_some_and(|level_path| !args.no_resume && ask_resume(level_path));
+  let mut controller = match Controller::new(&args, campaign.as_ref(), show_menu, replay.as_ref(), resume) {
     Ok(controller) => controller,
     Err(e) => {
       let error_message = format!("Could not create the controller: {}", e);
       error!("{error_message}");
       println!("{error_message}");
-      return;
+      return ExitCode::FAILURE;
     }
   };
   let mut quick_shutdowns = 0;
   let mut now = time::Instant::now();
   // Make sure to stop execution, if too many shutdowns happen in quick succession
   loop {
-    match controller.start() {
-      ExecutionState::Quit    => { controller.shutdown(); break },
-      ExecutionState::Restart => {
+    let exec_state = controller.start();
+    // Keep the progress of the campaign, if the level has been solved
+    if let (Some(campaign), Some(solved_turns)) = (campaign.as_mut(), controller.solved_turns) {
+      if let Err(e) = campaign.record(solved_turns) { error!("Could not write the profile: {}", e); }
+    }
+    match exec_state {
+      // Quitting a level chosen in the menu returns to the menu
+      ExecutionState::Quit if !use_menu || show_menu => { controller.shutdown(); break },
+      ExecutionState::Quit      |
+      ExecutionState::Restart   |
+      ExecutionState::NextLevel |
+      ExecutionState::Play(_)   => {
         controller.shutdown();
-        controller = match Controller::new(&args) {
+        // Restarting a level starts it over, so its autosave is gone
+        if let (ExecutionState::Restart, None, Some(level_path)) = (&exec_state, replay.as_ref(), campaign.as_ref().map_or(args.level.clone(), |campaign| Some(campaign.level_path()))) {
+          let autosave_path = State::get_autosave_file_path(level_path.as_str());
+          if Path::new(autosave_path.as_str()).exists() {
+            if let Err(e) = fs::remove_file(autosave_path.as_str()) { error!("Could not remove autosave `{}`: {}", autosave_path, e); }
+          }
+        }
+        resume = false;
+        match (exec_state, campaign.as_mut()) {
+          (ExecutionState::Quit       , _             ) => show_menu = true,
+          (ExecutionState::NextLevel  , Some(campaign)) => campaign.next(),
+          (ExecutionState::Play(level), Some(campaign)) => { campaign.select(level); show_menu = false },
+          _                                             => ()
+        }
+        controller = match Controller::new(&args, campaign.as_ref(), show_menu, replay.as_ref(), resume) {
           Ok(controller) => controller,
           Err(e)         => {
             error!("Could not create the controller: {}", e);
-            return;
+            return ExitCode::FAILURE;
           }
         };
       },
-      _                       => {
+      _                         => {
         // If less then QUICK_SHUTDOWN_IN_SECS time elapsed since the last shut down
         if now.elapsed() < time::Duration::from_secs(QUICK_SHUTDOWN_IN_SECS) { quick_shutdowns += 1; } else { quick_shutdowns = 0; }
         controller.shutdown();
+        // Continue where the game was autosaved, before it shut down
+        resume = true;
         if quick_shutdowns >= TOO_MANY_QUICK_SHUTDOWNS {
           error!("Execution stopped: Too many shutdowns ({}) in succession", quick_shutdowns);
-          break
+          return ExitCode::FAILURE;
         }
-        controller = match Controller::new(&args) {
+        controller = match Controller::new(&args, campaign.as_ref(), show_menu, replay.as_ref(), resume) {
           Ok(controller) => controller,
           Err(e)         => {
             error!("Could not create the controller: {}", e);
-            return;
+            return ExitCode::FAILURE;
           }
         };
         now = time::Instant::now();
       }
     }
   }
+  ExitCode::SUCCESS
 }
\ No newline at end of file
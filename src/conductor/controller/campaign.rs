@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const PROFILE_PATH  : &str = "connect.profile";
+const LEVEL_EXTENSION   : &str = "lvl";
+const MANIFEST_COMMENT  : &str = "#";
+
+/// Progress of the player: The best turn count of every solved level
+pub struct Profile {
+  profile_path: String,
+  best_turns  : HashMap<String,i32>
+}
+
+impl Profile {
+  /// Load the profile, if it exists, otherwise start with an empty one
+  pub fn new(profile_path: String) -> io::Result<Self> {
+    let best_turns = if Path::new(profile_path.as_str()).exists() { Profile::parse(fs::read_to_string(profile_path.as_str())?.as_str()) } else { HashMap::new() };
+    Ok(Self { profile_path, best_turns })
+  }
+
+  /// Every line of a profile holds the best turn count and the path of a solved level
+  fn parse(profile_string: &str) -> HashMap<String,i32> {
+    profile_string.lines()
+      .filter_map(|line| line.split_once(' '))
+      .filter_map(|(turns, level_path)| turns.parse().ok().map(|turns| (level_path.to_string(), turns)))
+      .collect()
+  }
+
+  fn to_profile_string(&self) -> String {
+    let mut level_paths: Vec<&String> = self.best_turns.keys().collect();
+    level_paths.sort();
+    level_paths.into_iter().map(|level_path| format!("{} {}\n", self.best_turns[level_path], level_path)).collect()
+  }
+
+  #[inline]
+  pub fn best_turns(&self, level_path: &str) -> Option<i32> { self.best_turns.get(level_path).copied() }
+
+  /// Remember the turn count, if it is the best for the level so far, and write the profile
+  pub fn record(&mut self, level_path: &str, turns: i32) -> io::Result<()> {
+    if self.best_turns(level_path).is_none_or(|best| turns < best) {
+      self.best_turns.insert(level_path.to_string(), turns);
+      fs::write(self.profile_path.as_str(), self.to_profile_string())?;
+    }
+    Ok(())
+  }
+}
+
+/// A sequence of levels played one after another
+pub struct Campaign {
+  levels : Vec<String>,
+  current: usize,
+  profile: Profile
+}
+
+impl Campaign {
+  /// Load the levels of a directory ordered by file name or of a manifest file listing one level path per line,
+  /// then continue with the first level not yet solved
+  pub fn new(campaign_path: &str, profile: Profile) -> io::Result<Self> {
+    let levels = if Path::new(campaign_path).is_dir() { Campaign::levels_in_directory(campaign_path)? } else { Campaign::levels_in_manifest(campaign_path)? };
+    if levels.is_empty() {
+      return Err(io::Error::new(io::ErrorKind::NotFound, format!("Campaign `{}` has no levels", campaign_path)));
+    }
+    let current = levels.iter().position(|level_path| profile.best_turns(level_path).is_none()).unwrap_or(0);
+    Ok(Self { levels, current, profile })
+  }
+
+  pub fn levels_in_directory(directory_path: &str) -> io::Result<Vec<String>> {
+    let mut levels: Vec<String> = fs::read_dir(directory_path)?
+      .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+      .filter(|path| path.is_file() && path.extension().is_some_and(|extension| extension == LEVEL_EXTENSION))
+      .map(|path| path.to_string_lossy().to_string())
+      .collect();
+    levels.sort();
+    Ok(levels)
+  }
+
+  /// Level paths in a manifest are relative to the manifest, empty lines and lines starting with `#` are skipped
+  fn levels_in_manifest(manifest_path: &str) -> io::Result<Vec<String>> {
+    let directory = Path::new(manifest_path).parent().unwrap_or(Path::new(""));
+    Ok(fs::read_to_string(manifest_path)?.lines()
+      .map(str::trim)
+      .filter(|line| !line.is_empty() && !line.starts_with(MANIFEST_COMMENT))
+      .map(|line| directory.join(line).to_string_lossy().to_string())
+      .collect())
+  }
+
+  #[inline]
+  pub fn levels(&self) -> &[String] { &self.levels }
+
+  #[inline]
+  pub fn best_turns(&self, level_path: &str) -> Option<i32> { self.profile.best_turns(level_path) }
+
+  #[inline]
+  pub fn level_path(&self) -> String { self.levels[self.current].clone() }
+
+  /// Number of the current level, starting at 1, and the number of all levels
+  #[inline]
+  pub fn progress(&self) -> (usize,usize) { (self.current+1, self.levels.len()) }
+
+  #[inline]
+  pub fn has_next(&self) -> bool { self.current+1 < self.levels.len() }
+
+  pub fn next(&mut self) {
+    if self.has_next() {
+      self.current += 1;
+    }
+  }
+
+  pub fn select(&mut self, level: usize) {
+    if level < self.levels.len() {
+      self.current = level;
+    }
+  }
+
+  #[inline]
+  pub fn record(&mut self, turns: i32) -> io::Result<()> { let level_path = self.level_path(); self.profile.record(level_path.as_str(), turns) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_round_trip() {
+      let profile = Profile { profile_path: PROFILE_PATH.to_string(), best_turns: HashMap::from([("levels/01-first.lvl".to_string(), 3), ("levels/my level.lvl".to_string(), 12)]) };
+      assert_eq!(profile.to_profile_string(), "3 levels/01-first.lvl\n12 levels/my level.lvl\n");
+      assert_eq!(Profile::parse(profile.to_profile_string().as_str()), profile.best_turns);
+    }
+
+    #[test]
+    fn campaign_in_directory() -> io::Result<()> {
+      let profile  = Profile { profile_path: PROFILE_PATH.to_string(), best_turns: HashMap::from([("levels/01-first.lvl".to_string(), 3)]) };
+      let campaign = Campaign::new("levels", profile)?;
+      assert_eq!(campaign.levels().first().map(String::as_str), Some("levels/01-first.lvl"));
+      assert!(campaign.levels().windows(2).all(|levels| levels[0] < levels[1]));
+      assert_eq!(campaign.level_path(), "levels/02-simple.lvl");
+      assert_eq!(campaign.progress(), (2, campaign.levels().len()));
+      Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::mpsc::{Sender, SyncSender, Receiver, self};
+use std::time;
+use crossterm::style::Color;
+
+use crate::common;
+
+use super::error;
+use super::output::{Char, Kind, Literal};
+use super::state::{header::LevelHeader, Direction, State};
+
+const SYNC_BUFFER_SIZE     : usize = 0;
+const SENDING_RATE_IN_MSECS: u64   = 1;
+const INITIAL_CURSOR_POS_X : u16   = 1;
+const INITIAL_CURSOR_POS_Y : u16   = 1;
+const INITIAL_BOARD_SIZE_W : u16   = u16::MAX;
+const INITIAL_BOARD_SIZE_H : u16   = u16::MAX;
+
+#[derive(PartialEq, Eq)]
+pub enum ControlEditorPayload { MoveCursor(Direction), SetCursorPosition((u16,u16)), SetBoardSize((u16,u16)), Place, Erase, NextPart, NextVariant, Save, Shutdown }
+
+#[derive(PartialEq, Eq)]
+pub enum EditorControlPayload { ClearTerminal, PrintChars(Vec<Char>), SetCursorPosition((u16,u16)) }
+
+pub struct Editor {
+  editor_control_send: Sender<EditorControlPayload>,
+  control_editor_recv: Receiver<ControlEditorPayload>,
+  level_path         : String,
+  header_string      : String,
+  board_size         : (u16,u16),
+  cursor_pos         : (u16,u16),
+  cells              : HashMap<(u16,u16),char>,
+  parts              : Vec<Vec<char>>,
+  part               : usize,
+  variant            : usize,
+  modified           : bool
+}
+
+impl Editor {
+  pub fn new(level_path: String) -> io::Result<(Self, SyncSender<ControlEditorPayload>, Receiver<EditorControlPayload>)> {
+    let (control_editor_send, control_editor_recv) = mpsc::sync_channel(SYNC_BUFFER_SIZE);
+    let (editor_control_send, editor_control_recv) = mpsc::channel();
+    let level_string = if Path::new(level_path.as_str()).exists() { fs::read_to_string(level_path.as_str())? } else { String::new() };
+    // Keep the header of the level as it is and only edit its grid
+    let (header_string, cells) = match LevelHeader::split(level_string.as_str()) {
+      Ok((_, grid_string, _)) => (level_string[..level_string.len()-grid_string.len()].to_string(), Editor::cells_from_level(grid_string)),
+      Err(e)                  => Err(io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?
+    };
+    Ok((Self {
+      editor_control_send,
+      control_editor_recv,
+      level_path,
+      header_string,
+      board_size: (INITIAL_BOARD_SIZE_W, INITIAL_BOARD_SIZE_H),
+      cursor_pos: (INITIAL_CURSOR_POS_X, INITIAL_CURSOR_POS_Y),
+      cells,
+      parts     : Editor::parts(),
+      part      : 0,
+      variant   : 0,
+      modified  : false
+    }, control_editor_send, editor_control_recv))
+  }
+
+  /// All parts that can be placed, each with its variants:
+  /// Walls and locks, Volatiles, ice, teleporters, gates and one part per set of directions with all its normal, wide, door, Rotor and key connector variants
+  fn parts() -> Vec<Vec<char>> {
+    fn first_char(literal: Literal) -> Option<char> { literal.to_string().chars().next().filter(|c| *c != '?') }
+    let mut parts: Vec<Vec<char>> = vec![
+      [Literal::Wall, Literal::Lock].into_iter().filter_map(first_char).collect(),
+      first_char(Literal::Volatile).into_iter().collect(),
+      first_char(Literal::Ice).into_iter().collect(),
+      (1..10).filter_map(|pair| first_char(Literal::Teleporter(pair))).collect(),
+      [8,4,2,1].into_iter().filter_map(|bit| first_char(Literal::Gate(bit))).collect()
+    ];
+    for directions in 1..16 {
+      parts.push(
+        [Kind::None, Kind::Wide, Kind::Door, Kind::Rotor, Kind::Key].into_iter()
+          .flat_map(|kind| (1..256).map(move |connectors| (connectors, kind)))
+          .filter(|(connectors,_)| (connectors & 15) | (connectors >> 4) == directions)
+          .filter_map(|(connectors,kind)| first_char(Literal::Object(connectors, kind)))
+          .collect());
+    }
+    parts
+  }
+
+  /// Map every character of a level onto its position, skipping empty space
+  fn cells_from_level(level: &str) -> HashMap<(u16,u16),char> {
+    level.lines().enumerate()
+      .flat_map(|(y,row)| row.chars().enumerate().map(move |(x,c)| ((x as u16+1, y as u16+1), c)))
+      .filter(|(_,c)| *c != ' ')
+      .collect()
+  }
+
+  /// Write all characters into rows, filling gaps with empty space
+  pub(super) fn level_from_cells(cells: &HashMap<(u16,u16),char>) -> String {
+    let h = cells.keys().map(|(_,y)| *y).max().unwrap_or(0);
+    (1..=h).map(|y| {
+      let w = cells.keys().filter(|(_,_y)| *_y == y).map(|(x,_)| *x).max().unwrap_or(0);
+      (1..=w).map(|x| *cells.get(&(x,y)).unwrap_or(&' ')).collect::<String>()
+    }).collect::<Vec<String>>().join("\n")
+  }
+
+  #[inline]
+  fn brush(&self) -> char { self.parts[self.part][self.variant] }
+
+  #[inline]
+  fn status_y(&self) -> Option<u16> { if self.board_size.1 == INITIAL_BOARD_SIZE_H { None } else { self.board_size.1.checked_sub(1) } }
+
+  fn print_status(&self) -> error::IOResult {
+    if let Some(y) = self.status_y() {
+      let status = format!("{}{} │ Part: {} │ Tab: Part, v: Variant, Enter: Place, x: Erase, s: Save, q: Quit", self.level_path, if self.modified { "*" } else { "" }, self.brush());
+      self.editor_control_send.send(EditorControlPayload::PrintChars(vec![
+        Char::new(Literal::String(format!("{:<1$}", status, usize::from(self.board_size.0))), (0,y), Some(Color::Yellow))
+      ]))?;
+    }
+    Ok(())
+  }
+
+  fn print_cell(&self, pos: (u16,u16)) -> error::IOResult {
+    self.editor_control_send.send(EditorControlPayload::PrintChars(vec![match self.cells.get(&pos) {
+      Some(c) => Char::new(Literal::String(c.to_string()), pos, Some(Color::White)),
+      None    => Char::new(Literal::Empty, pos, None)
+    }]))?;
+    Ok(())
+  }
+
+  fn clear_print_all(&self) -> error::IOResult {
+    self.editor_control_send.send(EditorControlPayload::ClearTerminal)?;
+    self.editor_control_send.send(EditorControlPayload::PrintChars(
+      self.cells.iter().map(|(pos,c)| Char::new(Literal::String(c.to_string()), *pos, Some(Color::White))).collect()
+    ))?;
+    self.print_status()?;
+    self.editor_control_send.send(EditorControlPayload::SetCursorPosition(self.cursor_pos))?;
+    Ok(())
+  }
+
+  /// Move the cursor, but keep it off the status line and the first row and column, which levels never use
+  fn set_cursor_position(&mut self, pos@(x,y): (u16,u16)) -> error::IOResult {
+    if x > 0 && y > 0 && x < self.board_size.0 && self.status_y().is_none_or(|status_y| y < status_y) {
+      self.cursor_pos = pos;
+      self.editor_control_send.send(EditorControlPayload::SetCursorPosition(self.cursor_pos))?;
+    }
+    Ok(())
+  }
+
+  fn move_cursor(&mut self, direction: Direction) -> error::IOResult {
+    if let Some(pos) = State::move_cursor_to(&self.cursor_pos, direction, self.board_size, false) {
+      self.set_cursor_position(pos)?;
+    }
+    Ok(())
+  }
+
+  fn set_board_size(&mut self, size: (u16,u16)) -> error::IOResult {
+    if self.board_size != size {
+      self.board_size = size;
+      self.clear_print_all()?;
+    }
+    Ok(())
+  }
+
+  fn place(&mut self) -> error::IOResult {
+    if self.cells.insert(self.cursor_pos, self.brush()) != Some(self.brush()) {
+      self.modified = true;
+      self.print_cell(self.cursor_pos)?;
+      self.print_status()?;
+    }
+    Ok(())
+  }
+
+  fn erase(&mut self) -> error::IOResult {
+    if self.cells.remove(&self.cursor_pos).is_some() {
+      self.modified = true;
+      self.print_cell(self.cursor_pos)?;
+      self.print_status()?;
+    }
+    Ok(())
+  }
+
+  fn next_part(&mut self) -> error::IOResult {
+    self.part    = (self.part + 1) % self.parts.len();
+    self.variant = 0;
+    self.print_status()
+  }
+
+  fn next_variant(&mut self) -> error::IOResult {
+    self.variant = (self.variant + 1) % self.parts[self.part].len();
+    self.print_status()
+  }
+
+  fn save(&mut self) -> error::IOResult {
+    fs::write(self.level_path.as_str(), format!("{}{}", self.header_string, Editor::level_from_cells(&self.cells)))?;
+    self.modified = false;
+    self.print_status()
+  }
+
+  /// Starts the main loop
+  pub fn maintain(mut self) -> error::IOResult {
+    let mut now = time::Instant::now();
+    self.clear_print_all()?;
+    loop {
+      // Make sure, we wait to keep input rate consistent
+      common::wait_minus_elapsed(time::Duration::from_millis(SENDING_RATE_IN_MSECS), now.elapsed());
+      now = time::Instant::now();
+
+      match self.control_editor_recv.recv()? {
+        ControlEditorPayload::MoveCursor(direction)    => self.move_cursor(direction)?,
+        ControlEditorPayload::SetCursorPosition(pos)   => self.set_cursor_position(pos)?,
+        ControlEditorPayload::SetBoardSize(board_size) => self.set_board_size(board_size)?,
+        ControlEditorPayload::Place                    => self.place()?,
+        ControlEditorPayload::Erase                    => self.erase()?,
+        ControlEditorPayload::NextPart                 => self.next_part()?,
+        ControlEditorPayload::NextVariant              => self.next_variant()?,
+        ControlEditorPayload::Save                     => self.save()?,
+        ControlEditorPayload::Shutdown                 => break
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Every character listed in the README is a variant of exactly one part
+    fn all_parts_available() {
+      let parts: Vec<char> = Editor::parts().into_iter().flatten().collect();
+      for c in "█◊░╵╶╷╴└┌┐┘│─├┬┤┴┼╹╺╻╸┖┕┗┍┎┏┒┑┓┚┙┛╿╽┃╼╾━┞┝┟┡┢┠┣┮┰┭┲┱┯┳┦┧┥┩┨┪┫┸┶┵┺┷┹┻╀┾╁┽╄╂╃╆┿╅╊╇╉╈╋╙╘╚╒╓╔╖╕╗╜╛╝║═╞╟╠╥╤╦╢╡╣╨╧╩╫╪╬▵▹▿◃╎╌╰╭╮╯▴▸▾◂╳①②③④⑤⑥⑦⑧⑨↑→↓←▓⊥⊢⊤⊣".chars() {
+        assert_eq!(parts.iter().filter(|part| **part == c).count(), 1, "Part `{}`", c);
+      }
+    }
+
+    #[test]
+    // Reading a level into cells and writing it back reproduces the level
+    fn level_round_trip() {
+      let level = "███\n█ ██████\n█┬ └ ┐╷█\n█┌┌ ┘ ┘█\n█╵┴ ┬ ┴█\n██████ █\n     ███";
+      assert_eq!(Editor::level_from_cells(&Editor::cells_from_level(level)), level);
+    }
+}
@@ -1,6 +1,6 @@
 use std::{sync::mpsc::{SendError, RecvError, TryRecvError}, fmt, error, io};
 
-use super::{output, input, state};
+use super::{editor, menu, output, input, state};
 
 pub enum IOError {
   Terminal(io::Error),
@@ -10,10 +10,21 @@ pub enum IOError {
   OutputControlPayloadSend(SendError<output::OutputControlPayload>),
   ControlStatePayloadSend(SendError<state::ControlStatePayload>),
   StateControlPayloadSend(SendError<state::StateControlPayload>),
+  ControlEditorPayloadSend(SendError<editor::ControlEditorPayload>),
+  EditorControlPayloadSend(SendError<editor::EditorControlPayload>),
+  ControlMenuPayloadSend(SendError<menu::ControlMenuPayload>),
+  MenuControlPayloadSend(SendError<menu::MenuControlPayload>),
   PayloadRecv(RecvError),
   TryPayloadRecv(TryRecvError),
+  #[cfg(feature = "duckdb")]
   DuckDB(duckdb::Error),
-  ParseLevelError
+  ParseLevelError(Vec<state::UnparsedObject>),
+  ParseLevelHeaderError(u16,String),
+  ParseReplayError(usize,String),
+  #[cfg(feature = "duckdb")]
+  SaveArchiveError(String,String),
+  #[cfg(feature = "duckdb")]
+  IncompatibleSaveError(String,String)
 }
 
 impl fmt::Debug for IOError {
@@ -26,10 +37,21 @@ impl fmt::Debug for IOError {
       Self::OutputControlPayloadSend(e) => write!(f, "OutputControlPayloadSend {}", e),
       Self::ControlStatePayloadSend(e)  => write!(f, "ControlStatePayloadSend {}",  e),
       Self::StateControlPayloadSend(e)  => write!(f, "StateControlPayloadSend {}",  e),
+      Self::ControlEditorPayloadSend(e) => write!(f, "ControlEditorPayloadSend {}", e),
+      Self::EditorControlPayloadSend(e) => write!(f, "EditorControlPayloadSend {}", e),
+      Self::ControlMenuPayloadSend(e)   => write!(f, "ControlMenuPayloadSend {}",   e),
+      Self::MenuControlPayloadSend(e)   => write!(f, "MenuControlPayloadSend {}",   e),
       Self::PayloadRecv(e)              => write!(f, "PayloadRecv {}",              e),
       Self::TryPayloadRecv(e)           => write!(f, "TryPayloadRecv {}",           e),
+      #[cfg(feature = "duckdb")]
       Self::DuckDB(e)                   => write!(f, "DuckDB {}",                   e),
-      Self::ParseLevelError             => write!(f, "ParseLevelError"               )
+      Self::ParseLevelError(objects)    => write!(f, "ParseLevelError {:?}",        objects),
+      Self::ParseLevelHeaderError(l,e)  => write!(f, "ParseLevelHeaderError {} {}", l, e),
+      Self::ParseReplayError(l,e)       => write!(f, "ParseReplayError {} {}",      l, e),
+      #[cfg(feature = "duckdb")]
+      Self::SaveArchiveError(p,e)       => write!(f, "SaveArchiveError {} {}",      p, e),
+      #[cfg(feature = "duckdb")]
+      Self::IncompatibleSaveError(p,e)  => write!(f, "IncompatibleSaveError {} {}", p, e)
     }
   }
 }
@@ -44,10 +66,24 @@ impl fmt::Display for IOError {
       Self::OutputControlPayloadSend(e) => write!(f, "OutputControlPayloadSend {}", e),
       Self::ControlStatePayloadSend(e)  => write!(f, "ControlStatePayloadSend {}",  e),
       Self::StateControlPayloadSend(e)  => write!(f, "StateControlPayloadSend {}",  e),
+      Self::ControlEditorPayloadSend(e) => write!(f, "ControlEditorPayloadSend {}", e),
+      Self::EditorControlPayloadSend(e) => write!(f, "EditorControlPayloadSend {}", e),
+      Self::ControlMenuPayloadSend(e)   => write!(f, "ControlMenuPayloadSend {}",   e),
+      Self::MenuControlPayloadSend(e)   => write!(f, "MenuControlPayloadSend {}",   e),
       Self::PayloadRecv(e)              => write!(f, "PayloadRecv {}",              e),
       Self::TryPayloadRecv(e)           => write!(f, "TryPayloadRecv {}",           e),
+      #[cfg(feature = "duckdb")]
       Self::DuckDB(e)                   => write!(f, "DuckDB {}",                   e),
-      Self::ParseLevelError             => write!(f, "ParseLevelError",              )
+      Self::ParseLevelError(objects)    => {
+        write!(f, "ParseLevelError {} characters could not be assigned to a shape:", objects.len())?;
+        objects.iter().try_for_each(|obj| write!(f, "\n  {}", obj))
+      },
+      Self::ParseLevelHeaderError(l,e)  => write!(f, "ParseLevelHeaderError in line {}: {}", l, e),
+      Self::ParseReplayError(l,e)       => write!(f, "ParseReplayError in line {}: {}",      l, e),
+      #[cfg(feature = "duckdb")]
+      Self::SaveArchiveError(p,e)       => write!(f, "SaveArchiveError at {}: {}",           p, e),
+      #[cfg(feature = "duckdb")]
+      Self::IncompatibleSaveError(p,e)  => write!(f, "IncompatibleSaveError at {}: {}",      p, e)
     }
   }
 }
@@ -62,10 +98,21 @@ impl error::Error for IOError {
       Self::OutputControlPayloadSend(ref e) => Some(e),
       Self::ControlStatePayloadSend(ref e)  => Some(e),
       Self::StateControlPayloadSend(ref e)  => Some(e),
+      Self::ControlEditorPayloadSend(ref e) => Some(e),
+      Self::EditorControlPayloadSend(ref e) => Some(e),
+      Self::ControlMenuPayloadSend(ref e)   => Some(e),
+      Self::MenuControlPayloadSend(ref e)   => Some(e),
       Self::PayloadRecv(ref e)              => Some(e),
       Self::TryPayloadRecv(ref e)           => Some(e),
+      #[cfg(feature = "duckdb")]
       Self::DuckDB(ref e)                   => Some(e),
-      Self::ParseLevelError                 => None
+      Self::ParseLevelError(_)              => None,
+      Self::ParseLevelHeaderError(_,_)      => None,
+      Self::ParseReplayError(_,_)           => None,
+      #[cfg(feature = "duckdb")]
+      Self::SaveArchiveError(_,_)           => None,
+      #[cfg(feature = "duckdb")]
+      Self::IncompatibleSaveError(_,_)      => None
     }
   }
 }
@@ -98,6 +145,22 @@ impl From<SendError<state::StateControlPayload>> for IOError {
   fn from(e: SendError<state::StateControlPayload>) -> Self { Self::StateControlPayloadSend(e) }
 }
 
+impl From<SendError<editor::ControlEditorPayload>> for IOError {
+  fn from(e: SendError<editor::ControlEditorPayload>) -> Self { Self::ControlEditorPayloadSend(e) }
+}
+
+impl From<SendError<editor::EditorControlPayload>> for IOError {
+  fn from(e: SendError<editor::EditorControlPayload>) -> Self { Self::EditorControlPayloadSend(e) }
+}
+
+impl From<SendError<menu::ControlMenuPayload>> for IOError {
+  fn from(e: SendError<menu::ControlMenuPayload>) -> Self { Self::ControlMenuPayloadSend(e) }
+}
+
+impl From<SendError<menu::MenuControlPayload>> for IOError {
+  fn from(e: SendError<menu::MenuControlPayload>) -> Self { Self::MenuControlPayloadSend(e) }
+}
+
 impl From<RecvError> for IOError {
   fn from(e: RecvError) -> Self { Self::PayloadRecv(e) }
 }
@@ -106,6 +169,7 @@ impl From<TryRecvError> for IOError {
   fn from(e: TryRecvError) -> Self { Self::TryPayloadRecv(e) }
 }
 
+#[cfg(feature = "duckdb")]
 impl From<duckdb::Error> for IOError {
   fn from(e: duckdb::Error) -> Self { Self::DuckDB(e) }
 }
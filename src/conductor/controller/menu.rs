@@ -0,0 +1,207 @@
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{Sender, SyncSender, Receiver, self};
+use std::time;
+use crossterm::style::Color;
+
+use crate::common;
+
+use super::error;
+use super::output::{Char, Literal};
+use super::state::{header::LevelHeader, Direction, State};
+
+const SYNC_BUFFER_SIZE     : usize = 0;
+const SENDING_RATE_IN_MSECS: u64   = 1;
+const INITIAL_BOARD_SIZE_W : u16   = u16::MAX;
+const INITIAL_BOARD_SIZE_H : u16   = u16::MAX;
+const LIST_POS_X           : u16   = 1;
+const LIST_POS_Y           : u16   = 3;
+const PREVIEW_GAP          : u16   = 4;
+
+#[derive(PartialEq, Eq)]
+pub enum ControlMenuPayload { MoveCursor(Direction), SetCursorPosition((u16,u16)), SetBoardSize((u16,u16)), Select, Shutdown }
+
+#[derive(PartialEq, Eq)]
+pub enum MenuControlPayload { ClearTerminal, PrintChars(Vec<Char>), SetCursorPosition((u16,u16)), Play(usize) }
+
+/// A level listed in the menu with its header, its best turn count, if it has been solved,
+/// and the error loading it, once it has been selected
+struct MenuEntry {
+  level_path: String,
+  name      : String,
+  header    : LevelHeader,
+  best_turns: Option<i32>,
+  preview   : Vec<String>,
+  checked   : Option<Result<(),String>>
+}
+
+impl MenuEntry {
+  fn new(level_path: &str, best_turns: Option<i32>) -> Self {
+    let name             = Path::new(level_path).file_name().map_or(level_path.to_string(), |name| name.to_string_lossy().to_string());
+    let level_string     = fs::read_to_string(level_path).unwrap_or_default();
+    let (header, preview) = match LevelHeader::split(level_string.as_str()) {
+      Ok((header, grid_string, _)) => (header, grid_string.lines().map(str::to_string).collect()),
+      Err(_)                       => (LevelHeader::default(), Vec::new())
+    };
+    Self { level_path: level_path.to_string(), name, header, best_turns, preview, checked: None }
+  }
+
+  /// Load the level once to find out whether it can be played
+  fn check(&mut self) -> &Result<(),String> {
+    let level_path = self.level_path.as_str();
+    self.checked.get_or_insert_with(|| if Path::new(level_path).exists() {
+      State::parse(level_path).map_err(|e| e.to_string())
+    } else {
+      Err("Level does not exist".to_string())
+    })
+  }
+
+  /// The title, author and difficulty of the level, as far as its header names them
+  fn to_heading_string(&self) -> Option<String> {
+    let heading = [
+      self.header.title.clone(),
+      self.header.author.as_ref().map(|author| format!("by {}", author)),
+      self.header.difficulty.as_ref().map(|difficulty| format!("({})", difficulty))
+    ].into_iter().flatten().collect::<Vec<_>>().join(" ");
+    (!heading.is_empty()).then_some(heading)
+  }
+
+  fn to_list_string(&self) -> String {
+    format!("{} {}{}",
+      match self.best_turns { Some(_) => if cfg!(windows) { "OK" } else { "✓ " }, None => "  " },
+      self.name,
+      self.best_turns.map_or(String::new(), |turns| format!(" ({} turns)", turns)))
+  }
+}
+
+pub struct Menu {
+  menu_control_send: Sender<MenuControlPayload>,
+  control_menu_recv: Receiver<ControlMenuPayload>,
+  board_size       : (u16,u16),
+  entries          : Vec<MenuEntry>,
+  selected         : usize
+}
+
+impl Menu {
+  /// Create the menu for all levels with their best turn count, starting with the `selected` level
+  pub fn new(levels: Vec<(String,Option<i32>)>, selected: usize) -> (Self, SyncSender<ControlMenuPayload>, Receiver<MenuControlPayload>) {
+    let (control_menu_send, control_menu_recv) = mpsc::sync_channel(SYNC_BUFFER_SIZE);
+    let (menu_control_send, menu_control_recv) = mpsc::channel();
+    let entries = levels.iter().map(|(level_path, best_turns)| MenuEntry::new(level_path, *best_turns)).collect();
+    (Self {
+      menu_control_send,
+      control_menu_recv,
+      board_size: (INITIAL_BOARD_SIZE_W, INITIAL_BOARD_SIZE_H),
+      entries,
+      selected
+    }, control_menu_send, menu_control_recv)
+  }
+
+  /// Number of list rows fitting on the terminal, below the heading and above the key help
+  #[inline]
+  fn visible_rows(&self) -> usize { usize::from(self.board_size.1.saturating_sub(LIST_POS_Y+2)).max(1) }
+
+  /// Index of the first listed level, such that the selected level is always visible
+  #[inline]
+  fn first_row(&self) -> usize { self.selected.saturating_sub(self.visible_rows()-1) }
+
+  fn print_all(&self) -> error::IOResult {
+    // Wait for the terminal size to know how much fits on it
+    if self.board_size == (INITIAL_BOARD_SIZE_W, INITIAL_BOARD_SIZE_H) { return Ok(()) }
+    self.menu_control_send.send(MenuControlPayload::ClearTerminal)?;
+    let solved = self.entries.iter().filter(|entry| entry.best_turns.is_some()).count();
+    let mut chars = vec![
+      Char::new(Literal::String(format!("Connect: {}/{} levels solved", solved, self.entries.len())), (LIST_POS_X,LIST_POS_Y-2), Some(Color::White))
+    ];
+    let first_row = self.first_row();
+    let list_w = self.entries.iter().map(|entry| entry.to_list_string().chars().count() as u16).max().unwrap_or(0);
+    for (row, (index, entry)) in self.entries.iter().enumerate().skip(first_row).take(self.visible_rows()).enumerate() {
+      let color = match (index == self.selected, entry.best_turns) {
+        (true , _      ) => Color::White,
+        (false, Some(_)) => Color::Green,
+        (false, None   ) => Color::DarkGrey
+      };
+      chars.push(Char::new(Literal::String(entry.to_list_string()), (LIST_POS_X,LIST_POS_Y+row as u16), Some(color)));
+    }
+    // Preview of the selected level, clipped to the terminal
+    if let Some(entry) = self.entries.get(self.selected) {
+      let preview_x = LIST_POS_X+list_w+PREVIEW_GAP;
+      let preview_w = usize::from(self.board_size.0.saturating_sub(preview_x));
+      if let Some(heading) = entry.to_heading_string() {
+        chars.push(Char::new(Literal::String(heading.chars().take(preview_w).collect()), (preview_x,LIST_POS_Y-2), Some(Color::White)));
+      }
+      if let Some(Err(e)) = &entry.checked {
+        chars.push(Char::new(Literal::String(format!("Cannot play this level: {}", e).chars().take(preview_w).collect()), (preview_x,LIST_POS_Y-1), Some(Color::Red)));
+      }
+      for (row, line) in entry.preview.iter().take(self.visible_rows()).enumerate() {
+        chars.push(Char::new(Literal::String(line.chars().take(preview_w).collect()), (preview_x,LIST_POS_Y+row as u16), Some(Color::DarkGrey)));
+      }
+    }
+    chars.push(Char::new(Literal::String("Up/Down: Select level, Enter: Play, q: Quit".to_string()), (LIST_POS_X,LIST_POS_Y+self.visible_rows() as u16+1), Some(Color::DarkGrey)));
+    self.menu_control_send.send(MenuControlPayload::PrintChars(chars))?;
+    self.menu_control_send.send(MenuControlPayload::SetCursorPosition((LIST_POS_X,LIST_POS_Y+(self.selected-first_row) as u16)))?;
+    Ok(())
+  }
+
+  fn select(&mut self, selected: usize) -> error::IOResult {
+    if selected != self.selected && selected < self.entries.len() {
+      self.selected = selected;
+      self.entries[selected].check();
+      self.print_all()?;
+    }
+    Ok(())
+  }
+
+  /// Play the selected level, unless it cannot be loaded
+  fn play(&mut self) -> error::IOResult {
+    if self.entries.get_mut(self.selected).is_some_and(|entry| entry.check().is_ok()) {
+      self.menu_control_send.send(MenuControlPayload::Play(self.selected))?;
+    }
+    Ok(())
+  }
+
+  fn move_cursor(&mut self, direction: Direction) -> error::IOResult {
+    match direction {
+      Direction::Up   => self.select(self.selected.saturating_sub(1)),
+      Direction::Down => self.select(self.selected+1),
+      _               => Ok(())
+    }
+  }
+
+  /// Select the level in the clicked row
+  fn set_cursor_position(&mut self, (_,y): (u16,u16)) -> error::IOResult {
+    if let Some(row) = y.checked_sub(LIST_POS_Y).map(usize::from).filter(|row| *row < self.visible_rows()) {
+      self.select(self.first_row()+row)?;
+    }
+    Ok(())
+  }
+
+  fn set_board_size(&mut self, size: (u16,u16)) -> error::IOResult {
+    if self.board_size != size {
+      self.board_size = size;
+      self.print_all()?;
+    }
+    Ok(())
+  }
+
+  /// Starts the main loop
+  pub fn maintain(mut self) -> error::IOResult {
+    let mut now = time::Instant::now();
+    if let Some(entry) = self.entries.get_mut(self.selected) { entry.check(); }
+    self.print_all()?;
+    loop {
+      // Make sure, we wait to keep input rate consistent
+      common::wait_minus_elapsed(time::Duration::from_millis(SENDING_RATE_IN_MSECS), now.elapsed());
+      now = time::Instant::now();
+
+      match self.control_menu_recv.recv()? {
+        ControlMenuPayload::MoveCursor(direction)    => self.move_cursor(direction)?,
+        ControlMenuPayload::SetCursorPosition(pos)   => self.set_cursor_position(pos)?,
+        ControlMenuPayload::SetBoardSize(board_size) => self.set_board_size(board_size)?,
+        ControlMenuPayload::Select                   => self.play()?,
+        ControlMenuPayload::Shutdown                 => break
+      }
+    }
+    Ok(())
+  }
+}
@@ -1,7 +1,7 @@
 use crossterm::{cursor, event};
 use crossterm::style::{Print, Color, SetForegroundColor, ResetColor};
 use crossterm::terminal;
-use std::fmt::Display;
+use std::fmt::{self, Display};
 use std::io::{Stdout, Write, self, stdout};
 use std::str::FromStr;
 use std::sync::mpsc::{Receiver, Sender, self, SyncSender};
@@ -16,16 +16,22 @@ const OUTPUT_RATE_IN_MSECS: u64   = 1;
 
 #[allow(dead_code)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum Kind { None, Wide, Door, Volatile, Removed }
+pub enum Kind { None, Wide, Door, Volatile, Rotor, Teleporter, Gate, Ice, Key, Lock, Removed }
 
-impl ToString for Kind {
-  fn to_string(&self) -> String {
+impl fmt::Display for Kind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     match self {
-      Self::None     => "None".to_string(),
-      Self::Wide     => "Wide".to_string(),
-      Self::Door     => "Door".to_string(),
-      Self::Volatile => "Volatile".to_string(),
-      Self::Removed  => "Removed".to_string()
+      Self::None       => write!(f, "None"),
+      Self::Wide       => write!(f, "Wide"),
+      Self::Door       => write!(f, "Door"),
+      Self::Volatile   => write!(f, "Volatile"),
+      Self::Rotor      => write!(f, "Rotor"),
+      Self::Teleporter => write!(f, "Teleporter"),
+      Self::Gate       => write!(f, "Gate"),
+      Self::Ice        => write!(f, "Ice"),
+      Self::Key        => write!(f, "Key"),
+      Self::Lock       => write!(f, "Lock"),
+      Self::Removed    => write!(f, "Removed")
     }
   }
 }
@@ -34,26 +40,39 @@ impl FromStr for Kind {
   type Err = String;
   fn from_str(s: &str) -> Result<Self, Self::Err> {
     match s {
-      "None"     => Ok(Self::None),
-      "Wide"     => Ok(Self::Wide),
-      "Door"     => Ok(Self::Door),
-      "Volatile" => Ok(Self::Volatile),
-      "Removed"  => Ok(Self::Removed),
-      _          => Err("Failed to parse string to type `Kind`".to_string())
+      "None"       => Ok(Self::None),
+      "Wide"       => Ok(Self::Wide),
+      "Door"       => Ok(Self::Door),
+      "Volatile"   => Ok(Self::Volatile),
+      "Rotor"      => Ok(Self::Rotor),
+      "Teleporter" => Ok(Self::Teleporter),
+      "Gate"       => Ok(Self::Gate),
+      "Ice"        => Ok(Self::Ice),
+      "Key"        => Ok(Self::Key),
+      "Lock"       => Ok(Self::Lock),
+      "Removed"    => Ok(Self::Removed),
+      _            => Err("Failed to parse string to type `Kind`".to_string())
     }
   }
 }
 
 #[allow(clippy::upper_case_acronyms,dead_code)]
 #[derive(PartialEq, Eq)]
-pub enum Literal { Unknown,Empty,Wall,Volatile,Object(i32,Kind),String(String) }
+pub enum Literal { Unknown,Empty,Wall,Lock,Volatile,Ice,Teleporter(i32),Gate(i32),Object(i32,Kind),String(String) }
 
 impl Display for Literal {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
       Literal::Empty                         => write!(f, " "),
       Literal::Wall                          => write!(f, "█"),
+      Literal::Lock                          => write!(f, "▓"),
       Literal::Volatile                      => write!(f, "◊"),
+      Literal::Ice                           => write!(f, "░"),
+      Literal::Teleporter(pair@1..=9)        => write!(f, "{}", char::from_u32('①' as u32 + *pair as u32 - 1).unwrap_or('?')),
+      Literal::Gate(0b00001000)              => write!(f, "↑"),
+      Literal::Gate(0b00000100)              => write!(f, "→"),
+      Literal::Gate(0b00000010)              => write!(f, "↓"),
+      Literal::Gate(0b00000001)              => write!(f, "←"),
 
       Literal::Object(0b00001000,Kind::None) => write!(f, "╵"),
       Literal::Object(0b10000000,Kind::Wide) => write!(f, "╹"),
@@ -178,6 +197,27 @@ impl Display for Literal {
       Literal::Object(0b10100101,Kind::Door) => write!(f, "╫"),
       Literal::Object(0b01011010,Kind::Door) => write!(f, "╪"),
       Literal::Object(0b11110000,Kind::Door) => write!(f, "╬"),
+
+      Literal::Object(0b00001000,Kind::Rotor) => write!(f, "▵"),
+      Literal::Object(0b00000100,Kind::Rotor) => write!(f, "▹"),
+      Literal::Object(0b00000010,Kind::Rotor) => write!(f, "▿"),
+      Literal::Object(0b00000001,Kind::Rotor) => write!(f, "◃"),
+      Literal::Object(0b00001010,Kind::Rotor) => write!(f, "╎"),
+      Literal::Object(0b00000101,Kind::Rotor) => write!(f, "╌"),
+      Literal::Object(0b00001100,Kind::Rotor) => write!(f, "╰"),
+      Literal::Object(0b00000110,Kind::Rotor) => write!(f, "╭"),
+      Literal::Object(0b00000011,Kind::Rotor) => write!(f, "╮"),
+      Literal::Object(0b00001001,Kind::Rotor) => write!(f, "╯"),
+      Literal::Object(0b00001101,Kind::Rotor) => write!(f, "▴"),
+      Literal::Object(0b00001110,Kind::Rotor) => write!(f, "▸"),
+      Literal::Object(0b00000111,Kind::Rotor) => write!(f, "▾"),
+      Literal::Object(0b00001011,Kind::Rotor) => write!(f, "◂"),
+      Literal::Object(0b00001111,Kind::Rotor) => write!(f, "╳"),
+
+      Literal::Object(0b00001000,Kind::Key) => write!(f, "⊥"),
+      Literal::Object(0b00000100,Kind::Key) => write!(f, "⊢"),
+      Literal::Object(0b00000010,Kind::Key) => write!(f, "⊤"),
+      Literal::Object(0b00000001,Kind::Key) => write!(f, "⊣"),
       Literal::String(s)                => write!(f, "{}", s),
       _                                 => write!(f, "?")
     }
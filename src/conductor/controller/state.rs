@@ -1,14 +1,28 @@
+// Without DuckDB the game is played without history and saves
+#![cfg_attr(not(feature = "duckdb"), allow(dead_code))]
+
+pub mod solver;
+pub mod check;
+pub mod header;
+pub mod replay;
+pub mod slots;
+#[cfg(feature = "duckdb")]
+pub mod archive;
+
 use core::panic;
-use std::{sync::mpsc::{Sender, SyncSender, Receiver, self}, time, cmp::max, fs, path::Path, str::FromStr};
+use std::{collections::HashMap, sync::mpsc::{Sender, SyncSender, Receiver, RecvError, RecvTimeoutError, self}, time, cmp::max, fmt, fs, path::Path, str::FromStr};
 use crossterm::style::Color;
+#[cfg(feature = "duckdb")]
 use duckdb::{Connection, params, OptionalExt, Statement};
-use zip_archive::Archiver;
 
-use crate::common;
+use crate::{common, engine};
+pub use crate::engine::{Completeness, Direction};
 
-use super::error;
+use super::{editor::Editor, error, output};
 
 type MoveObjectResult = Result<Option<(Vec<Object>,Vec<Object>,Option<i32>)>, error::IOError>;
+#[cfg(feature = "duckdb")]
+type HistoryTurn      = (i32,i32,Option<String>,bool);
 
 const SYNC_BUFFER_SIZE     : usize = 0;
 const SENDING_RATE_IN_MSECS: u64   = 1;
@@ -17,42 +31,28 @@ const INITIAL_CURSOR_POS_Y : u16   = 0;
 const INITIAL_BOARD_SIZE_W : u16   = u16::MAX;
 const INITIAL_BOARD_SIZE_H : u16   = u16::MAX;
 const UNDO_SIZE_IN_TURNS   : usize = 250;
-const TEMP_SAVE_PATH       : &str  = "temp-save";
+const HINT_SEARCH_LIMIT    : usize = 2_000;
+const HINT_SHAPE_COLOR     : Color = Color::Cyan;
+const HINT_TARGET_COLOR    : Color = Color::DarkCyan;
 const SAVE_FILE_PATH       : &str  = "connect";
+const SLOT_DIRECTORY_EXTENSION: &str = "saves";
+const AUTOSAVE_FILE_EXTENSION : &str = "autosave";
+const AUTOSAVE_INTERVAL_IN_SECS: u64 = 30;
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum Completeness { Complete, PartiallyComplete, Incomplete }
-
-impl ToString for Completeness {
-  fn to_string(&self) -> String {
-    match self {
-      Self::Complete          => "Complete".to_string(),
-      Self::PartiallyComplete => "PartiallyComplete".to_string(),
-      Self::Incomplete        => "Incomplete".to_string()
-    }
-  }
-}
-
-impl FromStr for Completeness {
-  type Err = String;
-  fn from_str(s: &str) -> Result<Self, Self::Err> {
-    match s {
-      "Complete"          => Ok(Self::Complete),
-      "PartiallyComplete" => Ok(Self::PartiallyComplete),
-      "Incomplete"        => Ok(Self::Incomplete),
-      _                   => Err("Failed to parse string to type `Completeness`".to_string())
-    }
-  }
-}
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ControlStatePayload { MoveCursor(Direction), SetCursorPosition((u16,u16)), Select, SetBoardSize((u16,u16)), Undo, Redo, PreviousBranch, NextBranch, Branches, GotoTurn(i32), Timeline, Save(String), Load, DeleteSlot, Export, Hint, Rotate, Shutdown }
 
-#[derive(PartialEq, Eq)]
-pub enum Direction { Up, UpRight, Right, DownRight, Down, DownLeft, Left, UpLeft }
+#[derive(Debug, PartialEq, Eq)]
+pub enum StateControlPayload { ClearTerminal, PrintObjects(Vec<Object>), SetCursorPosition((u16,u16)), MoveShape(Vec<Object>,Vec<Object>), ResizeTerminal((u16,u16)), TurnCounter(u16,i32,Completeness,header::LevelHeader), Branches(u16,Vec<Branch>), Timeline(u16,Vec<TimelineTurn>), Slots(u16,Vec<slots::Slot>,usize), Message(String,Color) }
 
-#[derive(PartialEq, Eq)]
-pub enum ControlStatePayload { MoveCursor(Direction), SetCursorPosition((u16,u16)), Select, SetBoardSize((u16,u16)), Undo, Redo, Save, Load, Shutdown }
+/// A branch of the history with the turn and completeness of its last turn, it is current, if it contains the current turn
+#[derive(Debug, PartialEq, Eq)]
+pub struct Branch { pub turn: i32, pub completeness: Option<Completeness>, pub current: bool }
 
+/// A turn of the current branch with the objects, which moved in it, and the direction they moved into,
+/// as well as the number of objects removed in it
 #[derive(Debug, PartialEq, Eq)]
-pub enum StateControlPayload { ClearTerminal, PrintObjects(Vec<Object>), SetCursorPosition((u16,u16)), MoveShape(Vec<Object>,Vec<Object>), ResizeTerminal((u16,u16)), TurnCounter(u16,i32,Completeness) }
+pub struct TimelineTurn { pub turn: i32, pub completeness: Option<Completeness>, pub moved: Vec<Object>, pub direction: Option<Direction>, pub removed: usize, pub current: bool }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Object { id: i32, shape: i32, color: Option<Color>, connectors: i32, kind: String, pos: (u16,u16) }
@@ -60,12 +60,23 @@ pub struct Object { id: i32, shape: i32, color: Option<Color>, connectors: i32,
 impl Object {
   fn new(id: i32, shape: i32, connectors: i32, kind: String, pos: (u16,u16)) -> Self { Object { id, shape, connectors, kind, pos, color: None } }
   fn new_with_color(id: i32, shape: i32, connectors: i32, kind: String, pos: (u16,u16), color: Option<Color>) -> Self { Object { id, shape, connectors, kind, pos, color } }
+  pub fn shape(&self) -> i32 { self.shape }
   pub fn connectors(&self) -> i32 { self.connectors }
   pub fn pos(&self) -> (u16,u16) { self.pos }
   pub fn color(&self) -> Option<Color> { self.color }
   pub fn kind(&self) -> String { self.kind.clone() }
 }
 
+/// A character of a level at (column, line), which could not be assigned to a shape
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnparsedObject { pub c: String, pub pos: (u16,u16), pub reason: String }
+
+impl fmt::Display for UnparsedObject {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "line {}, column {}: `{}`: {}", self.pos.1, self.pos.0, self.c, self.reason)
+  }
+}
+
 pub struct State {
   state_control_send: Sender<StateControlPayload>,
   control_state_recv: Receiver<ControlStatePayload>,
@@ -74,11 +85,25 @@ pub struct State {
   board_size        : (u16,u16),
   cursor_pos        : (u16,u16),
   selected_shape    : Option<i32>,
+  hint_shown        : bool,
+  hint_search       : Option<solver::Searcher>,
+  branches_shown    : bool,
+  timeline_shown    : bool,
+  slot_selected     : Option<usize>,
+  header            : header::LevelHeader,
+  level_checksum    : Option<u64>,
+  replay            : Option<replay::Replay>,
+  autosave_enabled  : bool,
+  session_changed   : bool,
+  resume            : bool,
+  board             : engine::Board,
+  turn              : i32,
+  #[cfg(feature = "duckdb")]
   db                : Connection
 }
 
 impl State {
-  pub fn new() -> duckdb::Result<(Self, SyncSender<ControlStatePayload>, Receiver<StateControlPayload>)> {
+  pub fn new() -> Result<(Self, SyncSender<ControlStatePayload>, Receiver<StateControlPayload>), error::IOError> {
     let (control_state_send, control_state_recv) = mpsc::sync_channel(SYNC_BUFFER_SIZE);
     let (state_control_send, state_control_recv) = mpsc::channel();
     Ok((Self {
@@ -89,262 +114,137 @@ impl State {
       board_size       : (INITIAL_BOARD_SIZE_W, INITIAL_BOARD_SIZE_H),
       cursor_pos       : (INITIAL_CURSOR_POS_X, INITIAL_CURSOR_POS_Y),
       selected_shape   : None,
+      hint_shown       : false,
+      hint_search      : None,
+      branches_shown   : false,
+      timeline_shown   : false,
+      slot_selected    : None,
+      header           : header::LevelHeader::default(),
+      level_checksum   : None,
+      replay           : None,
+      autosave_enabled : true,
+      session_changed  : false,
+      resume           : false,
+      board            : engine::Board::new(Vec::new()),
+      turn             : 0,
+      #[cfg(feature = "duckdb")]
       db               : Connection::open_in_memory()?
     }, control_state_send, state_control_recv))
   }
-  pub fn new_with_args(level_path: String, undo_size: usize) -> duckdb::Result<(Self, SyncSender<ControlStatePayload>, Receiver<StateControlPayload>)> {
+  pub fn new_with_args(level_path: String, undo_size: usize) -> Result<(Self, SyncSender<ControlStatePayload>, Receiver<StateControlPayload>), error::IOError> {
     let (mut state, control_state_recv, state_control_send) = State::new()?;
+    state.replay     = Some(replay::Replay::new(level_path.clone()));
     state.level_path = Some(level_path);
     state.undo_size  = undo_size;
     Ok((state, control_state_recv, state_control_send))
   }
+  /// Do not record a replay, e.g. while a replay is played
+  #[inline]
+  pub fn disable_replay(&mut self) { self.replay = None }
+  /// Do not autosave the game, e.g. while a replay is played
+  #[inline]
+  pub fn disable_autosave(&mut self) { self.autosave_enabled = false }
+  /// Continue with the autosaved game of the level, once the level is loaded
+  #[inline]
+  pub fn resume_autosave(&mut self) { self.resume = true }
   #[inline]
   pub fn cursor_position(&self) -> (u16,u16) { self.cursor_pos }
-  fn init_database(&self) -> duckdb::Result<()> { self.init_database_with_sequence_starters(1,1) }
-  fn init_database_with_sequence_starters(&self, start_id: i32, start_shape: i32) -> duckdb::Result<()> {
-    self.db.execute_batch(format!(r#"
+  /// The database keeps the history of the board and the board only in saves, the board itself is kept by the engine
+  #[cfg(feature = "duckdb")]
+  fn init_database(&self) -> duckdb::Result<()> { State::init_database_via(&self.db) }
+  #[cfg(feature = "duckdb")]
+  fn init_database_via(db: &Connection) -> duckdb::Result<()> {
+    db.execute_batch(r#"
     -- Object type enum
-    create type kind as enum ('None','Wide','Door','Volatile');
+    create type kind as enum ('None','Wide','Door','Volatile','Rotor','Teleporter','Gate','Ice','Key','Lock');
 
-    -- Is `x` ∈ {{ 0,…,65534 }}?
+    -- Is `x` ∈ { 0,…,65534 }?
     create macro is_inbound(x) as x between 0 and 65534;
 
-    -- Is `x` ∈ {{ 0,…,15 }}?
+    -- Is `x` ∈ { 0,…,15 }?
     create macro is_connectors(x) as x between 0 and 240;
 
-    -- Map character `c` to the corresponding connector number
-    create macro char_to_connectors(c) as
-      case
-        when c in ('█','◊') then   0
-        when c =   '╴'      then   1
-        when c =   '╸'      then ( 1 << 4)
-        when c =   '╷'      then   2
-        when c =   '╻'      then ( 2 << 4)
-        when c =   '┐'      then   3
-        when c in ('┒','╖') then ( 2 << 4) + 1
-        when c in ('┑','╕') then ( 1 << 4) + 2
-        when c in ('┓','╗') then ( 3 << 4)
-        when c =   '╶'      then   4
-        when c =   '╺'      then ( 4 << 4)
-        when c =   '─'      then   5
-        when c =   '╼'      then ( 4 << 4) + 1
-        when c =   '╾'      then ( 1 << 4) + 4
-        when c in ('━','═') then ( 5 << 4)
-        when c =   '┌'      then   6
-        when c in ('┍','╒') then ( 4 << 4) + 2
-        when c in ('┎','╓') then ( 2 << 4) + 4
-        when c in ('┏','╔') then ( 6 << 4)
-        when c =   '┬'      then   7
-        when c =   '┮'      then ( 4 << 4) + 3
-        when c =   '┭'      then ( 1 << 4) + 6
-        when c =   '┲'      then ( 6 << 4) + 1
-        when c =   '┱'      then ( 3 << 4) + 4
-        when c in ('┰','╥') then ( 2 << 4) + 5
-        when c in ('┯','╤') then ( 5 << 4) + 2
-        when c in ('┳','╦') then ( 7 << 4)
-        when c =   '╵'      then   8
-        when c =   '╹'      then ( 8 << 4)
-        when c =   '┘'      then   9
-        when c in ('┚','╜') then ( 8 << 4) + 1
-        when c in ('┙','╛') then ( 1 << 4) + 8
-        when c in ('┛','╝') then ( 9 << 4)
-        when c =   '│'      then  10
-        when c =   '╿'      then ( 8 << 4) + 2
-        when c =   '╽'      then ( 2 << 4) + 8
-        when c in ('┃','║') then (10 << 4)
-        when c =   '┤'      then  11
-        when c =   '┦'      then ( 8 << 4) + 3
-        when c =   '┧'      then ( 2 << 4) + 9
-        when c =   '┩'      then ( 9 << 4) + 2
-        when c =   '┪'      then ( 3 << 4) + 8
-        when c in ('┥','╡') then ( 1 << 4) + 10
-        when c in ('┨','╢') then (10 << 4) + 1
-        when c in ('┫','╣') then (11 << 4)
-        when c =   '└'      then  12
-        when c in ('┖','╙') then ( 8 << 4) + 4
-        when c in ('┕','╘') then ( 4 << 4) + 8
-        when c in ('┗','╚') then (12 << 4)
-        when c =   '┴'      then  13
-        when c =   '┶'      then ( 4 << 4) + 9
-        when c =   '┵'      then ( 1 << 4) + 12
-        when c =   '┺'      then (12 << 4) + 1
-        when c =   '┹'      then ( 9 << 4) + 4
-        when c in ('┸','╨') then ( 8 << 4) + 5
-        when c in ('┷','╧') then ( 5 << 4) + 8
-        when c in ('┻','╩') then (13 << 4)
-        when c =   '├'      then  14
-        when c =   '┞'      then ( 8 << 4) + 6
-        when c =   '┟'      then ( 2 << 4) + 12
-        when c =   '┡'      then (12 << 4) + 2
-        when c =   '┢'      then ( 6 << 4) + 8
-        when c in ('┝','╞') then ( 4 << 4) + 9
-        when c in ('┠','╟') then ( 9 << 4) + 4
-        when c in ('┣','╠') then (14 << 4)
-        when c =   '┼'      then  15
-        when c =   '╀'      then ( 8 << 4) + 7
-        when c =   '╈'      then ( 7 << 4) + 8
-        when c =   '┾'      then ( 4 << 4) + 11
-        when c =   '╉'      then (11 << 4) + 4
-        when c =   '╁'      then ( 2 << 4) + 13
-        when c =   '╇'      then (13 << 4) + 2
-        when c =   '┽'      then (14 << 4) + 1
-        when c =   '╊'      then ( 1 << 4) + 14
-        when c =   '╅'      then ( 3 << 4) + 12
-        when c =   '╄'      then (12 << 4) + 3
-        when c =   '╃'      then ( 9 << 4) + 6
-        when c =   '╆'      then ( 6 << 4) + 9
-        when c =  ('╂','╫') then (10 << 4) + 5
-        when c =  ('┿','╪') then ( 5 << 4) + 10
-        when c in ('╋','╬') then (15 << 4)
-      end;
-
-    -- Map character `c` to enum `kind`
-    create macro char_to_kind(c) as
-      case
-        when c = '◊'                                                                                                                    then 'Volatile'
-        when c in ('╸','╻','┒','┑','┓','╺','╼','╾','━','┍','┎','┏','┮','┰','┭','┲','┱','┯','┳','╹','┚',
-                   '┙','┛','╿','╽','┃','┦','┧','┥','┩','┨','┪','┫','┖','┕','┗','┸','┶','┵','┺','┷','┹',
-                   '┻','┞','┝','┟','┡','┢','┠','┣','╀','┾','╁','┽','╄','╂','╃','╆','┿','╅','╊','╇','╉','╈','╋')                         then 'Wide'
-        when c in ('╖','╕','╗','═','╒','╓','╔','╥','╤','╦','╜','╛','╝','║','╢','╡','╣','╙','╘','╚','╨','╧','╩','╞','╟','╠','╫','╪','╬') then 'Door'
-        else 'None'
-      end :: kind;
-
-    -- Returns true, if this and the other object at position (x,y) and (ox,oy) are vertically or horizontally adjacend and both connectors c and oc align and kind k = kind ok
-    create macro "connects?"(c,k,x,y,oc,ok,ox,oy) as
-      k = ok and ((c & 128) = 128 and (oc &  32) =  32 and (x,y) = (ox  ,oy+1))  -- Selected Up    Special Connector + Potential Down  Special Connector
-              or ((c &  64) =  64 and (oc &  16) =  16 and (x,y) = (ox-1,oy  ))  -- Selected Right Special Connector + Potential Left  Special Connector
-              or ((c &  32) =  32 and (oc & 128) = 128 and (x,y) = (ox  ,oy-1))  -- Selected Down  Special Connector + Potential Up    Special Connector
-              or ((c &  16) =  16 and (oc &  64) =  64 and (x,y) = (ox+1,oy  ))  -- Selected Left  Special Connector + Potential Right Special Connector
-              or ((c &   8) =   8 and (oc &   2) =   2 and (x,y) = (ox  ,oy+1))  -- Selected Up    Connector         + Potential Down  Connector
-              or ((c &   4) =   4 and (oc &   1) =   1 and (x,y) = (ox-1,oy  ))  -- Selected Right Connector         + Potential Left  Connector
-              or ((c &   2) =   2 and (oc &   8) =   8 and (x,y) = (ox  ,oy-1))  -- Selected Down  Connector         + Potential Up    Connector
-              or ((c &   1) =   1 and (oc &   4) =   4 and (x,y) = (ox+1,oy  )); -- Selected Left  Connector         + Potential Right Connector
-
-    create sequence object_seq_id start {start_id};
-    create sequence shape_seq_id start {start_shape};
+    -- The board as saved, it is written only to save and read only to load
     create table objects (
-      id         int  primary key default nextval('object_seq_id'),
-      shape      int  not null default nextval('shape_seq_id'),
+      id         int  primary key,
+      shape      int  not null,
       connectors int  not null check (is_connectors(connectors)),
       kind       kind not null,
       x          int  not null check (is_inbound(x)),
       y          int  not null check (is_inbound(y))
     );
 
-    -- Returns true, if an object at (ox,oy) with connectors oc and and kind ok is part of a complete shape
-    create macro "is complete?"(oc,ok,ox,oy) as
-      not (   (oc & 128) = 128 and not exists (select 1 from objects as _o where (_o.connectors &  32) =  32 and ok = _o.kind and (ox,oy) = (_o.x  ,_o.y+1))
-           or (oc &  64) =  64 and not exists (select 1 from objects as _o where (_o.connectors &  16) =  16 and ok = _o.kind and (ox,oy) = (_o.x-1,_o.y  ))
-           or (oc &  32) =  32 and not exists (select 1 from objects as _o where (_o.connectors & 128) = 128 and ok = _o.kind and (ox,oy) = (_o.x  ,_o.y-1))
-           or (oc &  16) =  16 and not exists (select 1 from objects as _o where (_o.connectors &  64) =  64 and ok = _o.kind and (ox,oy) = (_o.x+1,_o.y  ))
-           or (oc &   8) =   8 and not exists (select 1 from objects as _o where (_o.connectors &   2) =   2                  and (ox,oy) = (_o.x  ,_o.y+1))
-           or (oc &   4) =   4 and not exists (select 1 from objects as _o where (_o.connectors &   1) =   1                  and (ox,oy) = (_o.x-1,_o.y  ))
-           or (oc &   2) =   2 and not exists (select 1 from objects as _o where (_o.connectors &   8) =   8                  and (ox,oy) = (_o.x  ,_o.y-1))
-           or (oc &   1) =   1 and not exists (select 1 from objects as _o where (_o.connectors &   4) =   4                  and (ox,oy) = (_o.x+1,_o.y  )));
-
-    create macro "is partially complete?"() as (select count(distinct o.shape) from objects as o where o.connectors > 0) = 1;
-
-    create table undo (
-      turn       int  not null,
-      object_id  int  not null,
-      shape      int  not null,
-      connectors int  not null check (is_connectors(connectors)),
-      kind       kind not null,
-      x          int  not null check (is_inbound(x)),
-      y          int  not null check (is_inbound(y)),
-      primary key(turn, object_id)
+    -- Each connector bit with the direction (dx,dy) it points to, the bit it connects to and whether it is a special connector
+    create macro connector_bits() as table
+      select * from (values (128, 0,-1, 32, true ),
+                            ( 64, 1, 0, 16, true ),
+                            ( 32, 0, 1,128, true ),
+                            ( 16,-1, 0, 64, true ),
+                            (  8, 0,-1,  2, false),
+                            (  4, 1, 0,  1, false),
+                            (  2, 0, 1,  8, false),
+                            (  1,-1, 0,  4, false)) as b(bit,dx,dy,counterpart,special);
+
+    -- Every board reached while playing, as a tree of turns branching, whenever a turn is undone and another move is made
+    create table history (
+      node           int     not null,
+      parent         int,
+      turn           int     not null,
+      completeness   varchar,
+      selected_child int -- Child followed on redo, the last visited one
     );
 
-    create table redo (
-      turn       int  not null,
-      object_id  int  not null,
-      shape      int  not null,
-      connectors int  not null check (is_connectors(connectors)),
-      kind       kind not null,
-      x          int  not null check (is_inbound(x)),
-      y          int  not null check (is_inbound(y)),
-      primary key(turn, object_id)
-    )"#).as_str())
+    -- The objects changed by every turn in the history, as they were before and after the turn, null if they did not exist
+    create table changes (
+      node              int  not null,
+      object_id         int  not null,
+      before_shape      int,
+      before_connectors int  check (is_connectors(before_connectors)),
+      before_kind       kind,
+      before_x          int  check (is_inbound(before_x)),
+      before_y          int  check (is_inbound(before_y)),
+      after_shape       int,
+      after_connectors  int  check (is_connectors(after_connectors)),
+      after_kind        kind,
+      after_x           int  check (is_inbound(after_x)),
+      after_y           int  check (is_inbound(after_y)),
+      primary key(node, object_id)
+    );
+
+    -- The turn of the current board
+    create table head (
+      node int not null
+    )"#)
   }
 
-  fn load_level(&self, level_string: String) -> error::IOResult {
-    // Create temporary parsing table
-    self.db.execute(r"
-      create temporary table parsed_objects (
-        connectors int  not null check (is_connectors(connectors)),
-        kind       kind not null,
-        x          int  not null check (is_inbound(x)),
-        y          int  not null check (is_inbound(y))
-      );
-    ", params![])?;
-
-    // Parse the level string
-    self.db.execute(r"
-      insert into parsed_objects(connectors,kind,x,y)
-        select  char_to_connectors(chars[x]), char_to_kind(chars[x]), x, y
-        from    (select string_split_regex(?1,'(\r\n|[\r\n])') as rows),
-        lateral (select generate_subscripts(rows,1)            as y),
-        lateral (select string_split_regex(rows[y],'')         as chars),
-        lateral (select generate_subscripts(chars,1)           as x)
-        where chars[x] <> ' ';
-    ", params![level_string])?;
-
-    // Add walls
-    self.db.execute_batch(r#"
-      insert into objects(connectors,kind,x,y)
-      select * from parsed_objects as po where po.connectors = 0;
-
-      delete from parsed_objects
-      where connectors = 0;
-    "#)?;
-
-    // Keep forming objects into shapes until no more shapes are added
-    while self.db.execute(r#"
-      insert into objects(shape,connectors,kind,x,y)
-      with recursive form_shape(shape,connectors,kind,x,y) as (
-        (select nextval('shape_seq_id'), po.*
-         from   parsed_objects as po
-         where  po.connectors > 0
-         and    not exists (select 1
-                            from   objects as o
-                            where  (o.connectors,o.kind,o.x,o.y) = (po.connectors,po.kind,po.x,po.y))
-         limit 1)
-          union
-        select fs.shape, po.*
-        from   form_shape as fs, parsed_objects as po
-        where  "connects?"(fs.connectors, fs.kind, fs.x, fs.y, po.connectors, po.kind, po.x, po.y)
-      )
-      select fs.*
-      from   form_shape as fs
-    "#, params![])? > 0 {
-      self.db.execute(r#"
-        delete from parsed_objects as po
-        where exists (select 1
-                      from   objects as o
-                      where  (o.connectors,o.kind,o.x,o.y) = (po.connectors,po.kind,po.x,po.y))
-      "#, params![])?;
-    }
+  /// Load the grid of the level into the board and return the header of the level
+  fn load_level(&mut self, level_string: String) -> Result<header::LevelHeader, error::IOError> {
+    let (header, grid_string, grid_line) = header::LevelHeader::split(level_string.as_str())?;
 
-    if !self.db.query_row("select count(*) == 0 from parsed_objects as po", params![], |row| row.get(0))? {
-      Err(error::IOError::ParseLevelError)
-    } else {
-      self.db.execute("drop table parsed_objects", params![])?;
-      Ok(())
-    }
+    // Parse the grid into parts formed into shapes
+    self.board = engine::Board::parse(grid_string).map_err(|unknown| error::IOError::ParseLevelError(unknown.into_iter()
+      .map(|(c,(x,y))| UnparsedObject { c: c.to_string(), pos: (x,y+grid_line), reason: "Unknown character".to_string() })
+      .collect()))?;
+    self.turn  = 0;
+    Ok(header)
   }
 
-  fn populate_database(&self) -> error::IOResult {
+  fn populate_board(&mut self) -> error::IOResult {
     if let Some(level_path) = self.level_path.clone() {
       if !Path::new(level_path.as_str()).exists() {
         let error_message = "Level `{level_path}` does not exists";
         log::error!("{error_message}");
         panic!("{error_message}");
       }
-      self.load_level(fs::read_to_string(level_path)?)?;
+      let level_string    = fs::read_to_string(level_path)?;
+      #[cfg(feature = "duckdb")]
+      { self.level_checksum = Some(archive::level_checksum(level_string.as_str())); }
+      self.header         = self.load_level(level_string)?;
     }
     Ok(())
   }
 
+  #[cfg(feature = "duckdb")]
   fn shutdown_database(self) -> error::IOResult {
     if let Some((_,e)) = self.db.close().err() {
       Err(e)?;
@@ -354,32 +254,24 @@ impl State {
 
   fn clear_print_all(&self) -> error::IOResult {
     self.state_control_send.send(StateControlPayload::ClearTerminal)?;
-    if let Some(selected_shape) = self.selected_shape {
-      self.state_control_send.send(StateControlPayload::PrintObjects(
-        State::query_objects_via_statement(
-          self.db.prepare("select o.id, o.shape, o.connectors, o.kind::text, o.x, o.y from objects as o")?,
-          params![],
-          |row| {
-            let shape: i32 = row.get(1)?;
-            Ok(Object::new_with_color(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, (row.get(4)?,row.get(5)?), Some(if selected_shape == shape { Color::White } else { Color::DarkGrey })))
-          })?
-      ))?;
-    } else {
-      self.state_control_send.send(StateControlPayload::PrintObjects(
-        State::query_objects_via_statement(
-          self.db.prepare("select o.id, o.shape, o.connectors, o.kind::text, o.x, o.y from objects as o")?,
-          params![],
-          |row| {
-            Ok(Object::new_with_color(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, (row.get(4)?,row.get(5)?), Some(Color::DarkGrey)))
-          })?
-      ))?;
-    }
+    // Terrain comes first, so the parts on top of it are printed over it
+    let mut parts: Vec<&engine::Part> = self.board.parts().collect();
+    parts.sort_by_key(|part| !matches!(part.kind, engine::Kind::Teleporter | engine::Kind::Gate | engine::Kind::Ice));
+    self.state_control_send.send(StateControlPayload::PrintObjects(parts.into_iter()
+      .map(|part| State::object_from_part(part, Some(if self.selected_shape == Some(part.shape) { Color::White } else { Color::DarkGrey })))
+      .collect()))?;
     self.print_turn_counter()?;
+    #[cfg(feature = "duckdb")]
+    {
+      if self.branches_shown { self.print_branches()?; }
+      if self.timeline_shown { self.print_timeline()?; }
+      if let Some(slot_selected) = self.slot_selected { self.print_slots(slot_selected)?; }
+    }
     Ok(())
   }
 
   #[inline]
-  fn move_cursor_to(pos: &(u16,u16), direction: Direction, (w,h): (u16,u16), shape_selected: bool) -> Option<(u16,u16)> {
+  pub fn move_cursor_to(pos: &(u16,u16), direction: Direction, (w,h): (u16,u16), shape_selected: bool) -> Option<(u16,u16)> {
     if let (Some(x),Some(y)) = match (direction, shape_selected) {
       (Direction::UpLeft   , false) => (pos.0.checked_sub(1)                                                 , pos.1.checked_sub(1)                                                 ),
       (Direction::Up       , _    ) => (Some(pos.0)                                                          , pos.1.checked_sub(1)                                                 ),
@@ -392,14 +284,11 @@ impl State {
       _                             => (None                                                                 , None                                                                 )
     } { Some((x,y)) } else { None }
   }
-  fn object_by_pos(&self, (x,y): (u16,u16)) -> duckdb::Result<Option<Object>> {
-    self.db.query_row(r#"
-      select o.id, o.shape, o.connectors, o.kind::text
-      from   objects as o
-      where  (o.x,o.y) = (?1,?2)
-      order by o.id
-    "#, params![x,y], |row| Ok(Object::new(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, (x,y)))).optional()
+  /// The part at `pos`, or the terrain beneath it, if no part is on top of it
+  fn object_by_pos(&self, pos: (u16,u16)) -> Option<Object> {
+    self.board.part_at(pos).or_else(|| self.board.terrain_at(pos)).map(|part| State::object_from_part(part, None))
   }
+  #[cfg(feature = "duckdb")]
   fn query_objects_via_statement<T,F>(mut statement: Statement, params: &[&dyn duckdb::ToSql], f :F) -> duckdb::Result<Vec<T>>
   where
       F: FnMut(&duckdb::Row<'_>) -> duckdb::Result<T> {
@@ -410,242 +299,202 @@ impl State {
       .collect();
     if let Some(e) = err { Err(e) } else { Ok(objects) }
   }
-  fn objects_by_shape_with_color(&self, shape: i32, color: Option<Color>) -> duckdb::Result<Vec<Object>> {
-    State::query_objects_via_statement(
-      self.db.prepare("select o.id, o.shape, o.connectors, o.kind::text, o.x, o.y from objects as o where o.shape = ?1 order by o.id")?,
-      params![shape],
-      |row| Ok(Object::new_with_color(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, (row.get(4)?,row.get(5)?), color))
-    )
+  fn objects_by_shape_with_color(&self, shape: i32, color: Option<Color>) -> Vec<Object> {
+    self.board.shape_parts(shape).iter().map(|part| State::object_from_part(part, color)).collect()
   }
-  fn objects_by_shape_via_tx_with_color(tx: &duckdb::Transaction, shape: i32, color: Option<Color>) -> duckdb::Result<Vec<Object>> {
-    State::query_objects_via_statement(tx.prepare("select o.id, o.shape, o.connectors, o.kind::text, o.x, o.y from objects as o where o.shape = ?1 order by o.id")?,
-    params![shape],
-    |row| Ok(Object::new_with_color(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, (row.get(4)?,row.get(5)?), color)))
+  /// Move a `shape` by the step from `here` to `there` and add the board as a new turn to the history, if the shape moved
+  fn move_shape(&mut self, shape: i32, here: (u16,u16), there: (u16,u16)) -> MoveObjectResult {
+    let board_size = self.board_size;
+    self.play_turn(|board| board.move_shape(shape, here, there, board_size))
   }
-  fn objects_by_shape_via_tx(tx: &duckdb::Transaction, shape: i32) -> duckdb::Result<Vec<Object>> {
-    State::objects_by_shape_via_tx_with_color(tx, shape, None)
+
+  /// Rotate the Rotors of a `shape` with the cursor at `there` and add the board as a new turn to the history, if it has any
+  fn rotate_shape(&mut self, shape: i32, there: (u16,u16)) -> MoveObjectResult {
+    self.play_turn(|board| board.rotate_shape(shape, there))
   }
-  /// Move a `shape` by `(Δx,Δy)` in transaction `tx`
-  #[allow(non_snake_case)]
-  fn move_shape(tx                     : &duckdb::Transaction,
-                shape                  : i32,
-                (here_x,here_y)        : (u16,u16),
-                there@(there_x,there_y): (u16,u16),
-                (w,h)                  : (u16,u16),
-                undo_size              : usize) -> MoveObjectResult {
-    fn change_shape<F>(mut f: F, tx: &duckdb::Transaction, shape: i32, here_shape: Vec<Object>, there: (u16, u16)) -> MoveObjectResult
-    where F: FnMut() -> duckdb::Result<()> {
-      // Keep the completed shape positions
-      tx.execute("create temporary table completed_shape as select * from objects as o where o.shape = ?1", params![shape])?;
-
-      // Apply changes
-      f()?;
-
-      // Update shapes
-      while {
-        let new_shape: i32 = tx.query_row("select nextval('shape_seq_id')", params![], |row| row.get(0))?;
-        tx.execute(r#"
-        update objects
-        set    shape = ?2
-        where  (x,y) in (
-          with recursive one_shape(shape,connectors,kind,x,y) as (
-            (select o.shape, o.connectors, o.kind, o.x, o.y
-             from   objects as o
-             where  shape = ?1
-             limit  1)
-              union
-            select o.shape, o.connectors, o.kind, o.x, o.y
-            from   one_shape as os, objects as o
-            where  os.shape = o.shape
-            and    "connects?"(os.connectors, os.kind, os.x, os.y, o.connectors, o.kind, o.x, o.y)
-          )
-          select (os.x,os.y)
-          from   one_shape as os
-        )
-      "#, params![shape, new_shape])? > 0 } { }
-      // Query all objects that have previously been `shape` and set
-      let mut selected_shape = Some(shape);
-      let there_shape =
-        State::query_objects_via_statement(
-          tx.prepare(r#"
-            select cs.id, o.shape, o.connectors, o.kind::text, cs.x, cs.y
-            from   completed_shape as cs left join objects as o on (cs.x,cs.y) = (o.x,o.y);
-          "#)?, params![],
-          |row| {
-            let id = row.get(0)?;
-            let o_shape: Option<i32> = row.get(1)?;
-            let pos = (row.get(4)?,row.get(5)?);
-            if pos == there { selected_shape = o_shape }
-            if let Some(shape) = o_shape {
-              Ok(Object::new_with_color(id, shape, row.get(2)?, row.get(3)?, pos, Some(Color::DarkGrey)))
-            } else {
-              Ok(Object::new(id, 0, 0, "Removed".to_string(), pos))
-            }
-          }
-        )?;
-        // Clean up
-        tx.execute("drop table completed_shape", params![])?;
-      Ok(Some((here_shape,there_shape,selected_shape)))
+
+  /// Play a turn on the board by the engine and add the changes of the turn to the history, if any object changed
+  fn play_turn<F>(&mut self, f: F) -> MoveObjectResult
+  where F: FnOnce(&mut engine::Board) -> Option<engine::Moved> {
+    let moved  = f(&mut self.board);
+    let result = State::moved_objects(&self.board, moved);
+    if let Some((here_shape, there_shape, _)) = &result {
+      if State::is_shape_moved(here_shape, there_shape) {
+        self.turn += 1;
+        #[cfg(feature = "duckdb")]
+        self.add_history_turn(&self.board.changes())?;
+      }
     }
+    Ok(result)
+  }
 
-    let (Δx,Δy) = (i32::from(there_x)-i32::from(here_x),i32::from(there_y)-i32::from(here_y));
-    let here_shape = State::objects_by_shape_via_tx(tx, shape)?;
-    if !here_shape.is_empty() {
-      // Collision detection
-      let is_valid_move: bool = tx.query_row(r#"
-        select bool_and(is_inbound(o.x+?1) and is_inbound(o.y+?2)
-                        and o.x+?1 < ?4 and o.y+?2 < ?5
-                        and not exists (select 1
-                                        from   objects as _o
-                                        where  _o.shape <> ?3
-                                        and    (_o.x,_o.y) = (o.x+?1,o.y+?2)))
-               or any_value(o.kind) = 'Volatile'
-        from   objects as o
-        where  o.shape = ?3
-      "#, params![Δx,Δy,shape,w,h], |row| row.get(0))?;
-      if is_valid_move {
-        // Insert current state into undo
-        tx.execute_batch(r#"
-          insert into undo
-            select coalesce((select max(u.turn)+1 from undo as u), (select min(r.turn)-1 from redo as r), 0), o.*
-            from   objects as o;
-
-          delete from redo;
-        "#)?;
-        // Truncate all turns older than undo_size (in turns)
-        tx.execute(r#"
-          delete from undo
-          where  (select max(u.turn) from undo as u) - turn >= ?1
-        "#, params![undo_size])?;
-        // Move shape
-        tx.execute(r#"
-          update objects
-            set   x = x+?1, y = y+?2
-            where shape = ?3
-        "#, params![Δx,Δy,shape])?;
-        if tx.query_row(r#"select exists (select 1 from objects as o where o.shape = ?1)"#, params![shape], |row| row.get(0))? {
-          // True, if a volatile is on top of another object
-          if tx.query_row(r#"
-            select exists (
-              select 1
-              from   objects as o, objects as _o
-              where  o.shape = ?1
-              and    o.kind = 'Volatile'
-              and    _o.shape <> ?1
-              and    (o.x,o.y) = (_o.x,_o.y))
-            "#, params![shape], |row| row.get(0))? {
-              let o_shape = tx.query_row(r#"
-                select o.shape from objects as o where (o.x,o.y) = (?1,?2) and o.shape <> ?3
-              "#, params![there_x, there_y, shape], |row| row.get(0))?;
-              if let Some(shape) = o_shape {
-
-                return change_shape(|| {
-                  // Remove all objects at position the position the Volatile is moved to
-                  tx.execute(r#"
-                    delete from objects
-                    where  (x,y) = (?1,?2)
-                  "#, params![there_x,there_y])?;
-                  Ok(())
-                }, tx, shape, here_shape, there);
-              } else {
-                return Ok(None);
-              }
-          }
-          // Merge shapes, if any are adjacent and then do things
-          // If number of merged objects is larger than 0, check for doors
-          if tx.execute(r#"
-            update objects
-              set   shape = ?1
-              where shape <> ?1
-              and   shape in (select o.shape
-                              from   objects as o -- Potential merge candidates
-                              where  o.shape <> ?1
-                              and    exists (select 1
-                                             from   objects as _o -- Selected shape
-                                             where  _o.shape = ?1
-                                             and    "connects?"(_o.connectors, _o.kind, _o.x, _o.y, o.connectors, o.kind, o.x, o.y)))
-          "#, params![shape])? > 0 {
-            // If shape is completed and has any doors, open them.
-            if tx.query_row(r#"
-              select coalesce(bool_and("is complete?"(o.connectors,o.kind,o.x,o.y)) and bool_or(o.kind = 'Door'), false)
-              from   objects as o
-              where  o.shape = ?1
-            "#, params![shape], |row| row.get(0))? {
-              return change_shape(|| {
-                // Open all doors
-                tx.execute(r#"
-                  delete from objects
-                    where shape = ?1
-                    and   kind = 'Door'
-                    and   (connectors & 240) > 0
-                    and   (connectors &  15) = 0
-                "#, params![shape])?;
-                // Remove partial doors from objects
-                tx.execute(r#"
-                  update objects
-                    set   connectors = connectors & 15,
-                          kind = 'None'
-                    where shape = ?1
-                    and   kind = 'Door'
-                "#, params![shape])?;
-                Ok(())
-              }, tx, shape, here_shape, there);
-            }
-          }
-          return Ok(Some((here_shape, State::objects_by_shape_via_tx_with_color(tx, shape, Some(Color::White))?, Some(shape))));
-        }
+  /// The board saved in `db` for the engine
+  #[cfg(feature = "duckdb")]
+  fn board_via_db(db: &Connection) -> duckdb::Result<engine::Board> {
+    Ok(engine::Board::new(State::query_objects_via_statement(
+      db.prepare("select o.id, o.shape, o.connectors, o.kind::text, o.x, o.y from objects as o")?,
+      params![],
+      |row| Ok(engine::Part {
+        id        : row.get(0)?,
+        shape     : row.get(1)?,
+        connectors: row.get(2)?,
+        kind      : engine::Kind::from_str(row.get::<usize,String>(3)?.as_str()).unwrap_or(engine::Kind::None),
+        pos       : (row.get(4)?,row.get(5)?)
+      })
+    )?))
+  }
+
+  /// Replace the board saved in `db` with `board`
+  #[cfg(feature = "duckdb")]
+  fn write_board_via_db(db: &Connection, board: &engine::Board) -> duckdb::Result<()> {
+    db.execute("delete from objects", params![])?;
+    let mut insert = db.prepare("insert into objects(id,shape,connectors,kind,x,y) values (?1,?2,?3,?4,?5,?6)")?;
+    for part in board.parts() {
+      insert.execute(params![part.id, part.shape, part.connectors, part.kind.to_string(), part.pos.0, part.pos.1])?;
+    }
+    Ok(())
+  }
+
+  #[inline]
+  fn object_from_part(part: &engine::Part, color: Option<Color>) -> Object {
+    Object::new_with_color(part.id, part.shape, part.connectors, part.kind.to_string(), part.pos, color)
+  }
+
+  /// The objects of the shape before the turn `moved` on `board`, as they are now and the shape selected after it, if it moved
+  /// The terrain uncovered by the turn is appended to the objects as they are now
+  fn moved_objects(board: &engine::Board, moved: Option<engine::Moved>) -> Option<(Vec<Object>,Vec<Object>,Option<i32>)> {
+    let uncovered = |before: &[engine::Part]| before.iter()
+      .filter(|part| board.part_at(part.pos).is_none())
+      .filter_map(|part| board.terrain_at(part.pos))
+      .map(|terrain| State::object_from_part(terrain, Some(Color::DarkGrey)))
+      .collect::<Vec<Object>>();
+    match moved {
+      None                                                  => None,
+      Some(engine::Moved::Merged { before, shape })         => {
+        let after = board.shape_parts(shape);
+        Some((
+          before.iter().map(|part| State::object_from_part(part, None)).collect(),
+          after.iter().map(|part| State::object_from_part(part, Some(Color::White))).chain(uncovered(&before)).collect(),
+          Some(shape)
+        ))
+      },
+      Some(engine::Moved::Split { before, split, selected }) => {
+        // Every object that has previously been the split shape or an opened lock, as it is now, or removed
+        let there_shape = split.iter().map(|(part, now)| match now {
+          Some(now) => Object::new_with_color(part.id, now.shape, now.connectors, now.kind.to_string(), part.pos, Some(if selected == Some(now.shape) { Color::White } else { Color::DarkGrey })),
+          None      => Object::new(part.id, 0, 0, "Removed".to_string(), part.pos)
+        }).chain(uncovered(&before)).collect();
+        Some((before.iter().map(|part| State::object_from_part(part, None)).collect(), there_shape, selected))
+      }
+    }
+  }
+
+  /// Add the first turn with the `completeness` of its board to an empty history
+  #[cfg(feature = "duckdb")]
+  fn add_history_root(tx: &duckdb::Transaction, completeness: Option<Completeness>) -> duckdb::Result<()> {
+    if !tx.query_row("select exists (select 1 from head as hd)", params![], |row| row.get(0))? {
+      tx.execute("insert into history(node,parent,turn,completeness) values (1,null,0,?1)", params![completeness.map(|completeness| completeness.to_string())])?;
+      tx.execute("insert into head values (1)", params![])?;
+    }
+    Ok(())
+  }
+
+  /// Start the history with the board of the level as its first turn, unless it has any turn already
+  #[cfg(feature = "duckdb")]
+  fn start_history(&self) -> duckdb::Result<()> {
+    let mut db = self.db.try_clone()?;
+    let tx     = db.transaction()?;
+    State::add_history_root(&tx, Some(self.board.completeness()))?;
+    tx.commit()
+  }
+
+  /// Add the `changes` of the parts reported by the engine as a child of the current turn,
+  /// which keeps all other children as alternative branches, then truncate all turns older than undo_size (in turns)
+  #[cfg(feature = "duckdb")]
+  fn add_history_turn(&self, changes: &[(engine::Part,Option<engine::Part>)]) -> duckdb::Result<()> {
+    let mut db = self.db.try_clone()?;
+    let tx     = db.transaction()?;
+    // Without a started history, the completeness of the board before this turn is unknown
+    State::add_history_root(&tx, None)?;
+    let node: i32 = tx.query_row("select coalesce(max(h.node)+1,1) from history as h", params![], |row| row.get(0))?;
+    tx.execute(r#"
+      insert into history(node,parent,turn)
+        select ?1, h.node, h.turn+1
+        from   history as h, head as hd
+        where  h.node = hd.node
+    "#, params![node])?;
+    {
+      let mut insert = tx.prepare("insert into changes values (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12)")?;
+      for (before, after) in changes {
+        insert.execute(params![
+          node, before.id, before.shape, before.connectors, before.kind.to_string(), before.pos.0, before.pos.1,
+          after.as_ref().map(|after| after.shape), after.as_ref().map(|after| after.connectors), after.as_ref().map(|after| after.kind.to_string()),
+          after.as_ref().map(|after| after.pos.0), after.as_ref().map(|after| after.pos.1)
+        ])?;
       }
     }
-    Ok(None)
+    tx.execute("update history set selected_child = ?1 where node = (select hd.node from head as hd)", params![node])?;
+    tx.execute("update head set node = ?1", params![node])?;
+    tx.execute(r#"
+      delete from changes
+      where  node in (select h.node from history as h where h.turn < (select _h.turn from history as _h where _h.node = ?1) - ?2)
+    "#, params![node, self.undo_size])?;
+    tx.execute(r#"
+      delete from history
+      where  turn < (select h.turn from history as h where h.node = ?1) - ?2
+    "#, params![node, self.undo_size])?;
+    // The engine keeps the completeness up to date along the move, so the branches do not need to evaluate the board
+    tx.execute("update history set completeness = ?1 where node = ?2", params![self.board.completeness().to_string(), node])?;
+    tx.commit()
   }
 
-  /// Return turn counter and true, if the level is complete
-  fn turn_state(&self) -> Result<(i32,Completeness), duckdb::Error> {
-    self.db.query_row(r#"
-      select coalesce((select max(u.turn)+1 from undo as u), (select min(r.turn)-1 from redo as r), 0) as turn,
-             case
-               when (select bool_and("is complete?"(o.connectors,o.kind,o.x,o.y))
-                     from   objects as o
-                     where  o.connectors > 0)  then 'Complete'
-               when "is partially complete?"() then 'PartiallyComplete'
-               else 'Incomplete'
-             end as is_complete
-    "#, params![], |row| Ok((
-      row.get(0)?,
-      Completeness::from_str(row.get::<usize,String>(1)?.as_str()).unwrap_or(Completeness::Incomplete)
-    )))
+  /// True, if any object of a shape changed its position or connectors or got removed during a move
+  #[inline]
+  fn is_shape_moved(here_shape: &[Object], there_shape: &[Object]) -> bool {
+    here_shape.len() != there_shape.len() || here_shape.iter().enumerate().any(|(i,here)| here.pos != there_shape[i].pos || here.connectors != there_shape[i].connectors)
   }
 
+  /// Return turn counter and the completeness of the board, which the engine keeps up to date along every turn
+  #[inline]
+  fn turn_state(&self) -> (i32,Completeness) { (self.turn, self.board.completeness()) }
+
+  /// Lowest line of the board, the turn counter and the views are printed below it
+  #[inline]
+  fn board_bottom(&self) -> Option<u16> { self.board.parts().map(|part| part.pos.1).max() }
+
   /// Print the turn counter and an indicator, if the level is complete
   fn print_turn_counter(&self) -> error::IOResult {
-    let (turn_count,completeness) = self.turn_state()?;
+    let (turn_count,completeness) = self.turn_state();
     self.state_control_send.send(
       StateControlPayload::TurnCounter(
-        self.db.query_row("select max(o.y) from objects as o", params![], |row| row.get(0)).optional()?.map(|v_pos: u16| v_pos+1).unwrap_or(0),
+        self.board_bottom().map_or(0, |y| y+1),
         turn_count,
-        completeness
+        completeness,
+        self.header.clone()
       ))?;
     Ok(())
   }
 
   /// Move cursor in a `direction` and notify the updated position to the controller, if the cursor moved to a new position
   /// If the cursor has an object id selected, move the object with the object id as well, then notify the controller
+  /// The cursor follows the object under it, even if a teleporter relocates its shape
   fn move_cursor(&mut self, direction: Direction) -> error::IOResult {
     let cursor_here = self.cursor_position();
-    if let Some(cursor_there) = State::move_cursor_to(&cursor_here, direction, self.board_size, self.selected_shape.is_some()) {
+    if let Some(mut cursor_there) = State::move_cursor_to(&cursor_here, direction, self.board_size, self.selected_shape.is_some()) {
       let mut do_cursor_move = cursor_here != cursor_there;
       let mut do_shape_move  = false;
       let mut selected_shape = self.selected_shape;
-      let mut db             = self.db.try_clone()?;
-      let tx                 = db.transaction()?;
       if do_cursor_move {
         if let Some(shape) = selected_shape {
-          match State::move_shape(&tx, shape, cursor_here, cursor_there, self.board_size, self.undo_size) {
+          match self.move_shape(shape, cursor_here, cursor_there) {
             Ok(None)                                                => { do_cursor_move = false },
             Ok(Some((here_shape, there_shape, new_selected_shape))) => {
-              do_shape_move  = here_shape.len() != there_shape.len() || here_shape.iter().enumerate().any(|(i,here)| here.pos != there_shape[i].pos);
+              do_shape_move  = State::is_shape_moved(&here_shape, &there_shape);
               do_cursor_move = do_shape_move;
               selected_shape = new_selected_shape;
+              cursor_there   = here_shape.iter().find(|here| here.pos == cursor_here)
+                .and_then(|here| there_shape.iter().find(|there| there.id == here.id && there.kind != "Removed"))
+                .map_or(cursor_there, |there| there.pos);
               if do_shape_move {
                 self.state_control_send.send(StateControlPayload::MoveShape(here_shape,there_shape))?; // Note: An object always moves before the cursor.
               }
@@ -661,11 +510,10 @@ impl State {
           self.state_control_send.send(StateControlPayload::SetCursorPosition((self.cursor_pos.0, self.cursor_pos.1)))?;
         }
       }
-      tx.commit()?;
       if self.selected_shape != selected_shape {
         self.selected_shape = selected_shape;
         if let Some(shape) = self.selected_shape {
-          self.state_control_send.send(StateControlPayload::PrintObjects(self.objects_by_shape_with_color(shape, Some(Color::White))?))?;
+          self.state_control_send.send(StateControlPayload::PrintObjects(self.objects_by_shape_with_color(shape, Some(Color::White))))?;
         }
       }
       if do_shape_move { self.print_turn_counter()?; }
@@ -686,23 +534,80 @@ impl State {
   /// If an shape is selected, deselect the shape
   fn toggle_select_shape(&mut self) -> error::IOResult {
     if let Some(shape) = self.selected_shape {
-      self.state_control_send.send(StateControlPayload::PrintObjects(self.objects_by_shape_with_color(shape, Some(Color::DarkGrey))?))?;
+      self.state_control_send.send(StateControlPayload::PrintObjects(self.objects_by_shape_with_color(shape, Some(Color::DarkGrey))))?;
       self.selected_shape = None;
     } else {
-      self.selected_shape = self.object_by_pos(self.cursor_position())?.filter(|obj| obj.connectors != 0 || obj.kind == "Volatile").map(|obj| obj.shape);
+      self.selected_shape = self.object_by_pos(self.cursor_position()).filter(|obj| obj.connectors != 0 || obj.kind == "Volatile").map(|obj| obj.shape);
     }
     if let Some(shape) = self.selected_shape {
-      self.state_control_send.send(StateControlPayload::PrintObjects(self.objects_by_shape_with_color(shape, Some(Color::White))?))?;
+      self.state_control_send.send(StateControlPayload::PrintObjects(self.objects_by_shape_with_color(shape, Some(Color::White))))?;
     }
     Ok(())
   }
 
+  /// Rotate the Rotors of the selected shape, then notify the controller of the changed objects and the shape now selected
+  fn rotate(&mut self) -> error::IOResult {
+    let Some(shape) = self.selected_shape else { return Ok(()) };
+    let result = self.rotate_shape(shape, self.cursor_position())?;
+    if let Some((here_shape, there_shape, selected_shape)) = result {
+      self.state_control_send.send(StateControlPayload::MoveShape(here_shape,there_shape))?;
+      self.selected_shape = selected_shape;
+      if let Some(shape) = self.selected_shape {
+        self.state_control_send.send(StateControlPayload::PrintObjects(self.objects_by_shape_with_color(shape, Some(Color::White))))?;
+      }
+      self.print_turn_counter()?;
+    }
+    Ok(())
+  }
+
+  /// Search for the next move towards a (partially) complete board,
+  /// then highlight the shape to move and the position it is moved to, or only the shape to rotate.
+  /// The search is kept and resumed by the next hint, as long as the board is its first board or on the way to its best board
+  fn hint(&mut self) -> error::IOResult {
+    let mut searcher = self.hint_search.take().filter(|searcher| searcher.continues_from(&self.board)).unwrap_or_else(|| solver::Searcher::new(&self.board));
+    let mut search   = searcher.resume(HINT_SEARCH_LIMIT);
+    // The best board may no longer be on the way from this board, then start over from here
+    if searcher.next_move(&self.board).is_none() && !searcher.starts_at(&self.board) {
+      searcher = solver::Searcher::new(&self.board);
+      search   = searcher.resume(HINT_SEARCH_LIMIT);
+    }
+    let next_move = searcher.next_move(&self.board);
+    self.hint_search = Some(searcher);
+    if let Some(solver::Move { pos, action }) = next_move {
+      if let Some(hint_obj) = self.object_by_pos(pos) {
+        let hint_shape      = self.objects_by_shape_with_color(hint_obj.shape, Some(HINT_SHAPE_COLOR));
+        let mut hint_target = Vec::new();
+        let direction       = match action { solver::Action::Step(direction) => Some(direction), solver::Action::Rotate => None };
+        for obj in hint_shape.iter() {
+          if let Some(target_pos) = direction.and_then(|direction| State::move_cursor_to(&obj.pos, direction, self.board_size, true)) {
+            // Only mark positions not covered by any object
+            if self.object_by_pos(target_pos).is_none() {
+              hint_target.push(Object::new_with_color(obj.id, obj.shape, obj.connectors, obj.kind(), target_pos, Some(HINT_TARGET_COLOR)));
+            }
+          }
+        }
+        self.state_control_send.send(StateControlPayload::PrintObjects(hint_shape))?;
+        self.state_control_send.send(StateControlPayload::PrintObjects(hint_target))?;
+        self.hint_shown = true;
+        return Ok(());
+      }
+    }
+    log::info!("State: No hint found within {} boards", search.explored);
+    let message = match search.end {
+      solver::SearchEnd::Complete                                  => "No hint, the board is complete",
+      solver::SearchEnd::Unsolvable | solver::SearchEnd::Exhausted => "No hint, the board cannot be completed anymore",
+      solver::SearchEnd::LimitReached                              => "No hint found yet, press h to search further"
+    };
+    // Fall back to the hint of the level, if it has one
+    let message = match &self.header.hint { Some(hint) => format!("{} | Hint: {}", message, hint), None => message.to_string() };
+    self.state_control_send.send(StateControlPayload::Message(message, Color::Red))?;
+    Ok(())
+  }
+
   fn set_board_size(&mut self, size@(w,h): (u16,u16)) -> error::IOResult {
     let old_board_size = self.board_size;
     self.board_size =
-    if let Some((min_w, min_h)) = self.db.query_row(r#"
-      select max(o.x), max(o.y) from objects as o
-      "#, params![], |row| Ok((row.get(0)?, row.get(1)?))).optional()? {
+    if let Some((min_w, min_h)) = self.board.parts().map(|part| part.pos).reduce(|(max_x, max_y), (x, y)| (max(max_x, x), max(max_y, y))) {
         (max(min_w, w), max(min_h, h))
       } else { size };
     if old_board_size != self.board_size {
@@ -712,150 +617,627 @@ impl State {
     Ok(())
   }
 
+  /// Replace the board with the board of turn `node` in the history and continue from there:
+  /// Objects changed by turns between the current turn and their nearest common turn get their state before these turns,
+  /// unless turns between the common turn and `node` changed them, then they get their state after the last of these turns
+  #[cfg(feature = "duckdb")]
+  fn restore_turn(&mut self, node: i32) -> error::IOResult {
+    let mut db = self.db.try_clone()?;
+    let tx = db.transaction()?;
+    tx.execute(r#"
+      create temporary table restored_objects as
+      with recursive
+        up(node,parent,turn) as (
+          select h.node, h.parent, h.turn
+          from   history as h, head as hd
+          where  h.node = hd.node
+            union all
+          select h.node, h.parent, h.turn
+          from   up as u, history as h
+          where  h.node = u.parent
+        ),
+        down(node,parent,turn) as (
+          select h.node, h.parent, h.turn
+          from   history as h
+          where  h.node = ?1
+            union all
+          select h.node, h.parent, h.turn
+          from   down as d, history as h
+          where  h.node = d.parent
+        ),
+        redone_objects as (
+          select c.object_id, c.after_shape as shape, c.after_connectors as connectors, c.after_kind as kind, c.after_x as x, c.after_y as y
+          from   changes as c, down as d
+          where  c.node = d.node
+          and    d.node not in (select u.node from up as u)
+          qualify row_number() over (partition by c.object_id order by d.turn desc) = 1
+        ),
+        undone_objects as (
+          select c.object_id, c.before_shape as shape, c.before_connectors as connectors, c.before_kind as kind, c.before_x as x, c.before_y as y
+          from   changes as c, up as u
+          where  c.node = u.node
+          and    u.node not in (select d.node from down as d)
+          and    c.object_id not in (select ro.object_id from redone_objects as ro)
+          qualify row_number() over (partition by c.object_id order by u.turn asc) = 1
+        )
+      select * from redone_objects
+        union all
+      select * from undone_objects
+    "#, params![node])?;
+    let restored = State::query_objects_via_statement(
+      tx.prepare("select ro.object_id, ro.shape, ro.connectors, ro.kind::text, ro.x, ro.y from restored_objects as ro")?,
+      params![],
+      |row| Ok((row.get(0)?, match row.get::<usize,Option<i32>>(1)? {
+        Some(shape) => Some(engine::Part {
+          id        : row.get(0)?,
+          shape,
+          connectors: row.get(2)?,
+          kind      : engine::Kind::from_str(row.get::<usize,String>(3)?.as_str()).unwrap_or(engine::Kind::None),
+          pos       : (row.get(4)?,row.get(5)?)
+        }),
+        None        => None
+      })))?;
+    tx.execute_batch("drop table restored_objects")?;
+    tx.execute("update history set selected_child = ?1 where node = (select h.parent from history as h where h.node = ?1)", params![node])?;
+    tx.execute("update head set node = ?1", params![node])?;
+    let turn = tx.query_row("select h.turn from history as h where h.node = ?1", params![node], |row| row.get(0))?;
+    tx.commit()?;
+    self.board.restore(restored);
+    self.turn = turn;
+    self.selected_shape = None;
+    self.clear_print_all()?;
+    self.print_turn_counter()?;
+    Ok(())
+  }
+
+  #[cfg(feature = "duckdb")]
   fn undo(&mut self) -> error::IOResult {
     // If there are any turns to undo, undo it
-    if self.db.query_row("select exists (select * from undo as u)", params![], |row| row.get(0))? {
-      let mut db = self.db.try_clone()?;
-      let tx = db.transaction()?;
-      tx.execute_batch(r#"
-        insert into redo
-          select (select max(u.turn) from undo as u)+1, o.* from objects as o;
-
-        insert or replace into objects(id,shape,connectors,kind,x,y)
-          select columns(* exclude (turn)) from undo as u where u.turn = (select max(u.turn) from undo as u);
-
-        delete from objects
-          where id not in (select u.object_id from undo as u where u.turn = (select min(u.turn) from undo as u));
-
-        delete from undo
-          where turn = (select max(u.turn) from undo as u);
-      "#)?;
-      tx.commit()?;
-      self.selected_shape = None;
-      self.clear_print_all()?;
-      self.print_turn_counter()?;
+    if let Some(parent) = self.db.query_row(r#"
+      select p.node
+      from   history as h, head as hd, history as p
+      where  h.node = hd.node
+      and    p.node = h.parent
+    "#, params![], |row| row.get(0)).optional()? {
+      self.restore_turn(parent)?;
     }
     Ok(())
   }
 
+  #[cfg(feature = "duckdb")]
   fn redo(&mut self) -> error::IOResult {
-    // If there are any turns to redo, redo it
-    if self.db.query_row("select exists (select * from redo as r)", params![], |row| row.get(0))? {
-      let mut db = self.db.try_clone()?;
-      let tx = db.transaction()?;
-      tx.execute_batch(r#"
-        insert into undo
-        select (select min(r.turn) from redo as r)-1, o.* from objects as o;
-
-        insert or replace into objects(id,shape,connectors,kind,x,y)
-          select columns(* exclude (turn)) from redo as r where r.turn = (select min(r.turn) from redo as r);
-
-        delete from objects
-          where id not in (select r.object_id from redo as r where r.turn = (select min(r.turn) from redo as r));
-
-        delete from redo
-          where turn = (select min(r.turn) from redo as r);
-      "#)?;
-      tx.commit()?;
-      self.selected_shape = None;
-      self.clear_print_all()?;
-      self.print_turn_counter()?;
+    // If there are any turns to redo, redo the last visited one
+    if let Some(child) = self.db.query_row(r#"
+      select c.node
+      from   history as h, head as hd, history as c
+      where  h.node = hd.node
+      and    c.node = h.selected_child
+    "#, params![], |row| row.get(0)).optional()? {
+      self.restore_turn(child)?;
     }
     Ok(())
   }
 
+  /// Switch to the next (or previous) sibling of the nearest turn, which has one, then follow that branch to its last visited turn
+  #[cfg(feature = "duckdb")]
+  fn switch_branch(&mut self, next: bool) -> error::IOResult {
+    if let Some(branch) = self.db.query_row(format!(r#"
+      with recursive
+        ancestors(node,parent,depth) as (
+          select h.node, h.parent, 0
+          from   history as h, head as hd
+          where  h.node = hd.node
+            union all
+          select h.node, h.parent, a.depth+1
+          from   ancestors as a, history as h
+          where  h.node = a.parent
+        ),
+        sibling(node) as (
+          select s.node
+          from   ancestors as a, history as s
+          where  s.parent = a.parent
+          and    s.node {} a.node
+          order by a.depth, s.node {}
+          limit 1
+        ),
+        branch(node,selected_child) as (
+          select h.node, h.selected_child
+          from   sibling as s, history as h
+          where  h.node = s.node
+            union all
+          select h.node, h.selected_child
+          from   branch as b, history as h
+          where  h.node = b.selected_child
+        )
+      select b.node from branch as b where b.selected_child is null
+    "#, if next { ">" } else { "<" }, if next { "asc" } else { "desc" }).as_str(), params![], |row| row.get(0)).optional()? {
+      self.restore_turn(branch)?;
+    }
+    Ok(())
+  }
+
+  /// Every branch of the history ends in a turn without children
+  #[cfg(feature = "duckdb")]
+  fn branches(&self) -> duckdb::Result<Vec<Branch>> {
+    State::query_objects_via_statement(
+      self.db.prepare(r#"
+        with recursive line(node,selected_child) as (
+          select h.node, h.selected_child
+          from   history as h, head as hd
+          where  h.node = hd.node
+            union all
+          select h.node, h.selected_child
+          from   line as l, history as h
+          where  h.node = l.selected_child
+        )
+        select h.turn, h.completeness, exists (select 1 from line as l where l.node = h.node)
+        from   history as h
+        where  h.selected_child is null
+        order by h.node
+      "#)?,
+      params![],
+      |row| Ok(Branch {
+        turn        : row.get(0)?,
+        completeness: row.get::<usize,Option<String>>(1)?.and_then(|completeness| Completeness::from_str(completeness.as_str()).ok()),
+        current     : row.get(2)?
+      })
+    )
+  }
+
+  /// Position of views below the turn counter
   #[inline]
-  fn get_save_file_path(&self) -> String { format!("{}.sav", self.level_path.clone().map_or(SAVE_FILE_PATH.to_string(), |s| s.replace(".lvl", ""))) }
+  fn view_y_pos(&self) -> u16 { self.board_bottom().map_or(0, |y| y+3) }
+
+  #[cfg(feature = "duckdb")]
+  fn print_branches(&self) -> error::IOResult {
+    self.state_control_send.send(StateControlPayload::Branches(self.view_y_pos(), self.branches()?))?;
+    Ok(())
+  }
+
+  /// Show or hide the branches of the history instead of the timeline
+  #[cfg(feature = "duckdb")]
+  fn toggle_branches(&mut self) -> error::IOResult {
+    self.branches_shown = !self.branches_shown;
+    self.timeline_shown = false;
+    self.clear_print_all()
+  }
+
+  /// Node, turn, completeness of every turn of the current branch, from the oldest kept turn to the last visited one,
+  /// and whether it is the current turn
+  #[cfg(feature = "duckdb")]
+  fn current_branch(&self) -> duckdb::Result<Vec<HistoryTurn>> {
+    State::query_objects_via_statement(
+      self.db.prepare(r#"
+        with recursive
+          ancestors(node) as (
+            select hd.node from head as hd
+              union all
+            select h.parent
+            from   ancestors as a, history as h
+            where  h.node = a.node
+            and    h.parent is not null
+          ),
+          descendants(node) as (
+            select h.selected_child
+            from   history as h, head as hd
+            where  h.node = hd.node
+            and    h.selected_child is not null
+              union all
+            select h.selected_child
+            from   descendants as d, history as h
+            where  h.node = d.node
+            and    h.selected_child is not null
+          )
+        select h.node, h.turn, h.completeness, h.node = (select hd.node from head as hd)
+        from   history as h
+        where  h.node in (select a.node from ancestors as a union select d.node from descendants as d)
+        order by h.turn
+      "#)?,
+      params![],
+      |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+    )
+  }
+
+  /// Compare the board of turn `node` with the board of the turn before,
+  /// returning the objects moved and their direction as well as the number of removed objects
+  #[allow(non_snake_case)]
+  #[cfg(feature = "duckdb")]
+  fn turn_diff(&self, node: i32) -> duckdb::Result<(Vec<Object>,Option<Direction>,usize)> {
+    let moved = State::query_objects_via_statement(
+      self.db.prepare(r#"
+        select c.object_id, c.after_shape, c.after_connectors, c.after_kind::text, c.after_x, c.after_y, c.after_x-c.before_x, c.after_y-c.before_y
+        from   changes as c
+        where  c.node = ?1
+        and    (c.after_x,c.after_y) <> (c.before_x,c.before_y)
+        order by c.after_y, c.after_x
+      "#)?,
+      params![node],
+      |row| Ok((Object::new(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, (row.get(4)?, row.get(5)?)), (row.get::<usize,i32>(6)?, row.get::<usize,i32>(7)?)))
+    )?;
+    let removed: usize = self.db.query_row(r#"
+      select count(*)
+      from   changes as c
+      where  c.node = ?1
+      and    c.after_shape is null
+    "#, params![node], |row| row.get(0))?;
+    let direction = moved.first().and_then(|(_,(Δx,Δy))| match (Δx.signum(), Δy.signum()) {
+      ( 0,-1) => Some(Direction::Up),
+      ( 1, 0) => Some(Direction::Right),
+      ( 0, 1) => Some(Direction::Down),
+      (-1, 0) => Some(Direction::Left),
+      _       => None
+    });
+    Ok((moved.into_iter().map(|(obj,_)| obj).collect(), direction, removed))
+  }
+
+  /// The turns of the current branch fitting below the board, around the current turn
+  #[cfg(feature = "duckdb")]
+  fn timeline(&self) -> duckdb::Result<Vec<TimelineTurn>> {
+    let turns   = self.current_branch()?;
+    let rows    = usize::from(self.board_size.1.saturating_sub(self.view_y_pos()+2)).max(1);
+    let current = turns.iter().position(|(_,_,_,current)| *current).unwrap_or(0);
+    let first   = current.saturating_sub(rows/2).min(turns.len().saturating_sub(rows));
+    turns.into_iter().skip(first).take(rows).map(|(node, turn, completeness, current)| {
+      let (moved, direction, removed) = self.turn_diff(node)?;
+      Ok(TimelineTurn { turn, completeness: completeness.and_then(|completeness| Completeness::from_str(completeness.as_str()).ok()), moved, direction, removed, current })
+    }).collect()
+  }
+
+  #[cfg(feature = "duckdb")]
+  fn print_timeline(&self) -> error::IOResult {
+    self.state_control_send.send(StateControlPayload::Timeline(self.view_y_pos(), self.timeline()?))?;
+    Ok(())
+  }
+
+  /// Show or hide the timeline of the current branch instead of the branches
+  #[cfg(feature = "duckdb")]
+  fn toggle_timeline(&mut self) -> error::IOResult {
+    self.timeline_shown = !self.timeline_shown;
+    self.branches_shown = false;
+    self.clear_print_all()
+  }
 
-  fn save(&self) -> error::IOResult {
-    let save_file_path = self.get_save_file_path();
-    if Path::new(TEMP_SAVE_PATH).exists() {
-      fs::remove_dir_all(TEMP_SAVE_PATH)?;
+  /// Restore the turn of the current branch closest to `turn` in one step
+  #[cfg(feature = "duckdb")]
+  fn goto_turn(&mut self, turn: i32) -> error::IOResult {
+    let current_branch = self.current_branch()?;
+    if let Some((node,_,_,current)) = current_branch.iter().min_by_key(|(_,_turn,_,_)| (i64::from(*_turn)-i64::from(turn)).abs()) {
+      if !current {
+        self.restore_turn(*node)?;
+      }
     }
-    fs::create_dir(TEMP_SAVE_PATH)?;
-    self.db.execute(r#"export database 'temp-save' (encoding utf8)"#, params![])?;
+    Ok(())
+  }
 
-    let mut archiver = Archiver::new();
-    archiver.push(TEMP_SAVE_PATH);
-    archiver.set_destination(Path::new(&".".to_string()));
-    if let Err(e) = archiver.archive() {
-      log::error!("Save failed: {}", e);
-    } else {
-      if Path::new(&save_file_path).exists() {
-        fs::remove_file(&save_file_path)?;
+  /// Path of the level without its extension, which files belonging to the level start with
+  #[inline]
+  fn get_level_file_stem(&self) -> String { self.level_path.as_ref().map_or(SAVE_FILE_PATH.to_string(), |s| Path::new(s).with_extension("").display().to_string()) }
+
+  #[inline]
+  fn get_slot_directory(&self) -> String { format!("{}.{}", self.get_level_file_stem(), SLOT_DIRECTORY_EXTENSION) }
+
+  #[inline]
+  fn get_replay_file_path(&self) -> String { format!("{}.{}", self.get_level_file_stem(), replay::REPLAY_FILE_EXTENSION) }
+
+  /// Write the replay of everything played so far, if it is recorded
+  fn save_replay(&self) -> error::IOResult {
+    match &self.replay {
+      Some(replay) if !replay.steps.is_empty() => replay.save(self.get_replay_file_path().as_str()),
+      _                                        => Ok(())
+    }
+  }
+
+  /// Path of the game autosaved for the level at `level_path`
+  #[inline]
+  pub fn get_autosave_file_path(level_path: &str) -> String { Path::new(level_path).with_extension(AUTOSAVE_FILE_EXTENSION).display().to_string() }
+
+  /// Save the game of the level, if anything changed since the last autosave and the game can be saved
+  fn autosave(&mut self) -> error::IOResult {
+    #[cfg(feature = "duckdb")]
+    if let (Some(level_path), true, true) = (self.level_path.as_ref(), self.autosave_enabled, self.session_changed) {
+      // A failed autosave must not stop the game, it is tried again with the next change
+      match self.save_archive(State::get_autosave_file_path(level_path).as_str()) {
+        Ok(()) => self.session_changed = false,
+        Err(e) => log::error!("Autosave failed: {}", e)
       }
-      fs::rename(
-        Path::new(&format!("{}.zip", TEMP_SAVE_PATH)),
-        Path::new(&save_file_path)
-      )?;
     }
-    if Path::new(TEMP_SAVE_PATH).exists() {
-      fs::remove_dir_all(TEMP_SAVE_PATH)?;
+    Ok(())
+  }
+
+  /// Save the game into a new slot, named after the turn, if no `name` is given
+  #[cfg(feature = "duckdb")]
+  fn save(&mut self, name: String) -> error::IOResult {
+    self.save_replay()?;
+    let slot_directory = self.get_slot_directory();
+    fs::create_dir_all(slot_directory.as_str())?;
+    let (turn, completeness) = self.turn_state();
+    let name = if name.trim().is_empty() { format!("Turn {}", turn) } else { name.trim().to_string() };
+    let slot = slots::Slot::new(slot_directory.as_str(), name, turn, completeness);
+    let saved = self.save_archive(slot.save_path.as_str());
+    if saved.is_ok() { slot.write_meta()?; }
+    self.clear_print_all()?;
+    if let Err(e) = saved {
+      log::error!("Save failed: {}", e);
+      self.state_control_send.send(StateControlPayload::Message(format!("Cannot save: {}", e), Color::Red))?;
     }
     Ok(())
   }
 
-  fn load(&mut self) -> error::IOResult {
-    let save_file_path_string = self.get_save_file_path();
-    let save_file_path = Path::new(&save_file_path_string);
-    if Path::exists(save_file_path) {
-      if let Err(e) = zip_extract::extract(fs::File::open(Path::new(save_file_path))?, Path::new("."), false) {
-        log::error!("Load file at path {} failed: {}", &save_file_path_string, e);
-      } else {
-        let level_string = fs::read_to_string(Path::new(format!("{}/load.sql", TEMP_SAVE_PATH).as_str()))?;
-        // Load the level
-        self.db = Connection::open_in_memory()?;
-        self.init_database()?;
-        self.db.execute_batch(level_string.as_str())?;
-        // Get the current start_id and start_shape
-        let (start_id, start_shape) = self.db.query_row("select coalesce(max(o.id)+1,1), coalesce(max(o.shape),1) from objects as o", params![], |row| Ok((row.get(0)?, row.get(1)?))).unwrap_or((1,1));
-        // Reopen the database
-        self.db = Connection::open_in_memory()?;
-        self.init_database_with_sequence_starters(start_id, start_shape)?;
-        self.db.execute_batch(level_string.as_str())?;
+  /// Export the board with the database into a temporary directory of its own, verify its archive can be loaded and only then replace the save at `save_file_path`
+  #[cfg(feature = "duckdb")]
+  fn save_archive(&self, save_file_path: &str) -> error::IOResult {
+    let temp_directory   = archive::TempDirectory::new("save")?;
+    let export_directory = temp_directory.path().join(archive::EXPORT_DIRECTORY);
+    State::write_board_via_db(&self.db, &self.board)?;
+    self.db.execute(format!("export database {} (encoding utf8)", archive::sql_path(export_directory.as_path())).as_str(), params![])?;
+    archive::SaveVersion::current(self.level_checksum).write(export_directory.as_path())?;
+    archive::Session { cursor_pos: self.cursor_pos, selected_shape: self.selected_shape }.write(export_directory.as_path())?;
+    let archive_path = archive::archive(export_directory.as_path(), temp_directory.path())?;
+    State::import_archive(archive_path.as_path(), self.level_checksum)?;
+    archive::replace_atomically(archive_path.as_path(), Path::new(save_file_path))?;
+    Ok(())
+  }
+
+  /// Import the archive at `archive_path` into a new database, if it is compatible with the level with `level_checksum`
+  #[cfg(feature = "duckdb")]
+  fn import_archive(archive_path: &Path, level_checksum: Option<u64>) -> Result<(Connection, Option<archive::Session>), error::IOError> {
+    let temp_directory                  = archive::TempDirectory::new("load")?;
+    let (version, session, load_string) = archive::extract(archive_path, temp_directory.path())?;
+    let load_string                     = version.migrate(load_string, level_checksum).map_err(|msg| error::IOError::IncompatibleSaveError(archive_path.display().to_string(), msg))?;
+    let db = Connection::open_in_memory()?;
+    State::init_database_via(&db)?;
+    db.execute_batch(load_string.as_str())?;
+    Ok((db, session))
+  }
+
+  /// Replace the board and the database only, once the save at `save_file_path` is imported completely
+  #[cfg(feature = "duckdb")]
+  fn load(&mut self, save_file_path: &str) -> error::IOResult {
+    if Path::new(save_file_path).exists() {
+      match State::import_archive(Path::new(save_file_path), self.level_checksum) {
+        Ok((db, session)) => {
+          self.board          = State::board_via_db(&db)?;
+          self.turn           = db.query_row("select coalesce((select h.turn from history as h, head as hd where h.node = hd.node), 0)", params![], |row| row.get(0))?;
+          self.db             = db;
+          self.selected_shape = None;
+          if let Some(session) = session {
+            self.cursor_pos     = session.cursor_pos;
+            self.selected_shape = session.selected_shape;
+          }
+          self.state_control_send.send(StateControlPayload::SetCursorPosition(self.cursor_pos))?;
+          self.clear_print_all()?;
+        },
+        Err(e)            => {
+          log::error!("Load file at path {} failed: {}", save_file_path, e);
+          self.clear_print_all()?;
+          self.state_control_send.send(StateControlPayload::Message(match e {
+            error::IOError::IncompatibleSaveError(_, msg) => format!("Cannot load this save: {}", msg),
+            e                                             => format!("Cannot load this save: {}", e)
+          }, Color::Red))?;
+        }
       }
-      if Path::new(TEMP_SAVE_PATH).exists() {
-        fs::remove_dir_all(TEMP_SAVE_PATH)?;
+    }
+    Ok(())
+  }
+
+  /// The board as level, which parses into the same shapes again
+  fn board_to_level_string(&self) -> String {
+    // Terrain comes first, so the parts on top of it take its cells
+    let mut parts: Vec<&engine::Part> = self.board.parts().collect();
+    parts.sort_by_key(|part| !matches!(part.kind, engine::Kind::Teleporter | engine::Kind::Gate | engine::Kind::Ice));
+    let cells = parts.into_iter()
+      .map(|part| {
+        let literal = match (part.connectors, output::Kind::from_str(part.kind.to_string().as_str()).unwrap_or(output::Kind::None)) {
+          (_         , output::Kind::Volatile  ) => output::Literal::Volatile,
+          (_         , output::Kind::Teleporter) => output::Literal::Teleporter(-part.shape),
+          (_         , output::Kind::Gate      ) => output::Literal::Gate(engine::GATE_SHAPE - part.shape),
+          (_         , output::Kind::Ice       ) => output::Literal::Ice,
+          (_         , output::Kind::Lock      ) => output::Literal::Lock,
+          (0         , _                       ) => output::Literal::Wall,
+          (connectors, kind                    ) => output::Literal::Object(connectors, kind)
+        };
+        (part.pos, literal.to_string().chars().next().unwrap_or(' '))
+      })
+      .collect::<HashMap<(u16,u16),char>>();
+    Editor::level_from_cells(&cells)
+  }
+
+  /// Export the board as level next to the saves, to share it as a new puzzle
+  fn export(&self) -> error::IOResult {
+    let slot_directory = self.get_slot_directory();
+    fs::create_dir_all(slot_directory.as_str())?;
+    let export_path = slots::export_path(slot_directory.as_str());
+    fs::write(export_path.as_str(), self.board_to_level_string())?;
+    self.state_control_send.send(StateControlPayload::Message(format!("Exported the board to {}", export_path), Color::Green))?;
+    Ok(())
+  }
+
+  #[cfg(feature = "duckdb")]
+  fn print_slots(&self, slot_selected: usize) -> error::IOResult {
+    self.state_control_send.send(StateControlPayload::Slots(self.view_y_pos(), slots::Slot::list(self.get_slot_directory().as_str())?, slot_selected))?;
+    Ok(())
+  }
+
+  /// Show or hide the saved games
+  #[cfg(feature = "duckdb")]
+  fn toggle_slots(&mut self) -> error::IOResult {
+    self.slot_selected = if self.slot_selected.is_some() { None } else { Some(0) };
+    self.clear_print_all()
+  }
+
+  /// Select the previous or next slot
+  #[cfg(feature = "duckdb")]
+  fn move_slot_selection(&mut self, direction: Direction) -> error::IOResult {
+    if let Some(slot_selected) = self.slot_selected {
+      let slot_count = slots::Slot::list(self.get_slot_directory().as_str())?.len();
+      let selected   = match direction {
+        Direction::Up   => slot_selected.saturating_sub(1),
+        Direction::Down => (slot_selected+1).min(slot_count.saturating_sub(1)),
+        _               => slot_selected
+      };
+      if selected != slot_selected {
+        self.slot_selected = Some(selected);
+        self.print_slots(selected)?;
       }
-      self.selected_shape = None;
-      self.clear_print_all()?;
     }
     Ok(())
   }
 
+  /// Load the selected slot and hide the saved games
+  /// A replay cannot reproduce the board of a save, so the replay recorded so far is written and the recording ends
+  #[cfg(feature = "duckdb")]
+  fn load_selected_slot(&mut self) -> error::IOResult {
+    if let Some(slot) = self.slot_selected.and_then(|slot_selected| slots::Slot::list(self.get_slot_directory().as_str()).ok()?.into_iter().nth(slot_selected)) {
+      self.slot_selected = None;
+      self.save_replay()?;
+      self.disable_replay();
+      self.session_changed = true;
+      self.load(slot.save_path.as_str())?;
+    }
+    Ok(())
+  }
+
+  #[cfg(feature = "duckdb")]
+  fn delete_selected_slot(&mut self) -> error::IOResult {
+    if let Some(slot_selected) = self.slot_selected {
+      let slots = slots::Slot::list(self.get_slot_directory().as_str())?;
+      if let Some(slot) = slots.get(slot_selected) {
+        slot.delete()?;
+        self.slot_selected = Some(slot_selected.min(slots.len().saturating_sub(2)));
+        self.clear_print_all()?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Load the level at `level_path` once to report errors before the game starts
+  pub fn parse(level_path: &str) -> error::IOResult {
+    let (mut state, _, _) = State::new()?;
+    state.level_path = Some(level_path.to_string());
+    state.populate_board()
+  }
+
+  /// Load the level and search for a short, not necessarily the shortest, sequence of shape moves that completes it
+  pub fn solve(mut self, limit: usize) -> Result<solver::Search, error::IOError> {
+    self.populate_board()?;
+    Ok(self.search(limit))
+  }
+
   /// Starts the main loop
   pub fn maintain(mut self) -> error::IOResult {
     let mut now = time::Instant::now();
+    let started = now;
+    #[cfg(feature = "duckdb")]
     self.init_database()?;
-    self.populate_database()?;
+    self.populate_board()?;
+    #[cfg(feature = "duckdb")]
+    self.start_history()?;
     self.state_control_send.send(StateControlPayload::SetCursorPosition((self.cursor_pos.0, self.cursor_pos.1)))?;
     self.clear_print_all()?;
+    #[cfg(feature = "duckdb")]
+    if let (true, Some(level_path)) = (self.resume, self.level_path.clone()) {
+      // A replay starts from the level, so a resumed game is not recorded and the replay of the game before is kept
+      self.disable_replay();
+      self.load(State::get_autosave_file_path(level_path.as_str()).as_str())?;
+    }
+    let mut autosaved = now;
     loop {
       // Make sure, we wait to keep input rate consistent
       common::wait_minus_elapsed(time::Duration::from_millis(SENDING_RATE_IN_MSECS), now.elapsed());
       now = time::Instant::now();
 
-      match self.control_state_recv.recv()? {
+      // Autosave periodically, even while waiting for the next payload
+      if autosaved.elapsed() >= time::Duration::from_secs(AUTOSAVE_INTERVAL_IN_SECS) {
+        self.autosave()?;
+        autosaved = time::Instant::now();
+      }
+      let payload = match self.control_state_recv.recv_timeout(time::Duration::from_secs(AUTOSAVE_INTERVAL_IN_SECS).saturating_sub(autosaved.elapsed())) {
+        Ok(payload)                         => payload,
+        Err(RecvTimeoutError::Timeout)      => continue,
+        Err(RecvTimeoutError::Disconnected) => Err(RecvError)?
+      };
+      // Browsing the saved games is not recorded, loading one of them ends the recording
+      if let (Some(replay), None) = (self.replay.as_mut(), self.slot_selected) {
+        replay.record(started.elapsed().as_millis() as u64, &payload);
+      }
+      // Anything changing the board, the cursor or the selection is autosaved
+      self.session_changed |= replay::Replay::is_recorded(&payload);
+      // Remove a shown hint, before anything else happens
+      if self.hint_shown && payload != ControlStatePayload::Hint {
+        self.hint_shown = false;
+        self.clear_print_all()?;
+      }
+      // Remove shown saved games, unless they are browsed
+      if self.slot_selected.is_some() && !matches!(payload,
+        ControlStatePayload::MoveCursor(_) | ControlStatePayload::Select | ControlStatePayload::Load | ControlStatePayload::DeleteSlot) {
+        self.slot_selected = None;
+        self.clear_print_all()?;
+      }
+      // Remove shown branches or timeline, unless the history is browsed
+      if (self.branches_shown || self.timeline_shown) && !matches!(payload,
+        ControlStatePayload::Undo | ControlStatePayload::Redo | ControlStatePayload::PreviousBranch | ControlStatePayload::NextBranch |
+        ControlStatePayload::Branches | ControlStatePayload::GotoTurn(_) | ControlStatePayload::Timeline) {
+        self.branches_shown = false;
+        self.timeline_shown = false;
+        self.clear_print_all()?;
+      }
+
+      match payload {
+        #[cfg(feature = "duckdb")]
+        ControlStatePayload::MoveCursor(direction)
+          if self.slot_selected.is_some()             => self.move_slot_selection(direction)?,
+        #[cfg(feature = "duckdb")]
+        ControlStatePayload::Select
+          if self.slot_selected.is_some()             => self.load_selected_slot()?,
         ControlStatePayload::MoveCursor(direction)    => self.move_cursor(direction)?,
         ControlStatePayload::SetCursorPosition(pos)   => self.set_cursor_position(pos)?,
         ControlStatePayload::Select                   => self.toggle_select_shape()?,
         ControlStatePayload::SetBoardSize(board_size) => self.set_board_size(board_size)?,
+        #[cfg(feature = "duckdb")]
         ControlStatePayload::Undo                     => self.undo()?,
+        #[cfg(feature = "duckdb")]
         ControlStatePayload::Redo                     => self.redo()?,
-        ControlStatePayload::Save                     => self.save()?,
-        ControlStatePayload::Load                     => self.load()?,
+        #[cfg(feature = "duckdb")]
+        ControlStatePayload::PreviousBranch           => self.switch_branch(false)?,
+        #[cfg(feature = "duckdb")]
+        ControlStatePayload::NextBranch               => self.switch_branch(true)?,
+        #[cfg(feature = "duckdb")]
+        ControlStatePayload::Branches                 => self.toggle_branches()?,
+        #[cfg(feature = "duckdb")]
+        ControlStatePayload::GotoTurn(turn)           => self.goto_turn(turn)?,
+        #[cfg(feature = "duckdb")]
+        ControlStatePayload::Timeline                 => self.toggle_timeline()?,
+        #[cfg(feature = "duckdb")]
+        ControlStatePayload::Save(name)               => self.save(name)?,
+        #[cfg(feature = "duckdb")]
+        ControlStatePayload::Load                     => self.toggle_slots()?,
+        #[cfg(feature = "duckdb")]
+        ControlStatePayload::DeleteSlot               => self.delete_selected_slot()?,
+        // Without DuckDB the game keeps no history and cannot be saved
+        #[cfg(not(feature = "duckdb"))]
+        ControlStatePayload::Undo | ControlStatePayload::Redo | ControlStatePayload::PreviousBranch | ControlStatePayload::NextBranch |
+        ControlStatePayload::Branches | ControlStatePayload::GotoTurn(_) | ControlStatePayload::Timeline |
+        ControlStatePayload::Save(_) | ControlStatePayload::Load | ControlStatePayload::DeleteSlot
+                                                      => self.state_control_send.send(StateControlPayload::Message("History and saves need a build with DuckDB".to_string(), Color::Red))?,
+        ControlStatePayload::Export                   => self.export()?,
+        ControlStatePayload::Hint                     => self.hint()?,
+        ControlStatePayload::Rotate                   => self.rotate()?,
         ControlStatePayload::Shutdown                 => break
       }
     }
+    self.save_replay()?;
+    self.autosave()?;
+    #[cfg(feature = "duckdb")]
     self.shutdown_database()?;
     Ok(())
   }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "duckdb"))]
+#[allow(clippy::identity_op)]
 mod tests {
     use core::panic;
-    use std::thread::{self, JoinHandle};
+    use std::{collections::HashSet, thread::{self, JoinHandle}};
 
     use super::*;
 
@@ -885,11 +1267,11 @@ mod tests {
     }
 
     #[test]
-    // Initialize and populate the database and see, if the expected number of objects are created
+    // Initialize the database and populate the board
     fn initalize_and_populate_database() -> error::IOResult {
-      let (state, _, _) = State::new()?;
+      let (mut state, _, _) = State::new()?;
       state.init_database()?;
-      state.populate_database()?;
+      state.populate_board()?;
       Ok(())
     }
 
@@ -901,16 +1283,18 @@ mod tests {
       Ok(())
     }
 
-    // Helper function that adds an object to the initialized database of state
-    fn add_object(state: &State, shape: i32, conectors: i32, kind: String, x: u16, y: u16) -> duckdb::Result<usize> { state.db.execute(r#"insert into objects(shape,connectors,kind,x,y) select ?1,?2,?3,?4,?5"#, params![shape,conectors,kind,x,y]) }
+    // Helper function that adds an object with the next id to the board of state and returns its id
+    fn add_object(state: &mut State, shape: i32, connectors: i32, kind: String, x: u16, y: u16) -> i32 {
+      let id      = state.board.parts().map(|part| part.id).max().unwrap_or(0)+1;
+      let kind    = engine::Kind::from_str(kind.as_str()).unwrap_or(engine::Kind::None);
+      state.board = engine::Board::new(state.board.parts().cloned().chain([engine::Part { id, shape, connectors, kind, pos: (x,y) }]).collect());
+      id
+    }
 
-    // Wrapper function to query an object by shape via transaction
-    fn object_by_id(state: &State, shape: i32) -> duckdb::Result<Option<Object>> {
-      state.db.query_row(r#"
-        select o.id, o.shape, o.connectors, o.kind::text, o.x, o.y
-        from   objects as o
-        where  o.shape = ?1
-      "#, params![shape], |row| Ok(Object::new(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, (row.get(4)?, row.get(5)?)))).optional() }
+    // Wrapper function to get the first object of a shape
+    fn object_by_id(state: &State, shape: i32) -> Option<Object> {
+      state.board.shape_parts(shape).first().map(|part| State::object_from_part(part, None))
+    }
 
     // Wait for dummy thread
     fn wait_for_dummy_thread(dummy_thread: JoinHandle<()>) {
@@ -929,11 +1313,11 @@ mod tests {
       const CONNECTORS: i32 = 15;
       const X: u16          = 2;
       const Y: u16          = 3;
-      let (state, _, _) = State::new()?;
+      let (mut state, _, _) = State::new()?;
       state.init_database()?;
-      assert_eq!(add_object(&state, SHAPE, CONNECTORS, "None".to_string(), X, Y)?, 1);
-      assert_eq!(object_by_id(&state,1)?, Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y))));
-      assert_eq!(state.object_by_pos((X,Y))?, Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y))));
+      assert_eq!(add_object(&mut state, SHAPE, CONNECTORS, "None".to_string(), X, Y), 1);
+      assert_eq!(object_by_id(&state,1), Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y))));
+      assert_eq!(state.object_by_pos((X,Y)), Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y))));
       Ok(())
     }
 
@@ -978,7 +1362,7 @@ mod tests {
         }
         fn assert_received_turn_counter(dummy_recv: &Receiver<StateControlPayload>, expected_y_pos: u16, expected_turn: i32, expected_complete: Completeness) {
           match dummy_recv.recv() {
-            Ok(StateControlPayload::TurnCounter(y_pos,turn,complete)) =>  {
+            Ok(StateControlPayload::TurnCounter(y_pos,turn,complete,_)) =>  {
               assert_eq!((y_pos,turn,complete),(expected_y_pos,expected_turn,expected_complete));
             },
             Ok(payload)                                            => panic!("Did not receive TurnCounter: {:?}", payload),
@@ -1011,7 +1395,7 @@ mod tests {
       });
 
       state.init_database()?;
-      assert_eq!(add_object(&state, SHAPE, CONNECTORS, "None".to_string(), X, Y)?, 1);
+      assert_eq!(add_object(&mut state, SHAPE, CONNECTORS, "None".to_string(), X, Y), 1);
       assert_eq!(state.cursor_position(), (INITIAL_CURSOR_POS_X, INITIAL_CURSOR_POS_Y));
       assert_eq!(state.selected_shape, None);
       state.toggle_select_shape()?;
@@ -1027,51 +1411,136 @@ mod tests {
       state.move_cursor(Direction::Down)?;
       state.toggle_select_shape()?;
       assert_eq!(state.cursor_position(), (INITIAL_CURSOR_POS_X+2, INITIAL_CURSOR_POS_Y+3));
-      assert_eq!(object_by_id(&state,1)?, Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y))));
+      assert_eq!(object_by_id(&state,1), Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y))));
       assert_eq!(state.selected_shape, Some(1));
       state.move_cursor(Direction::Down)?;
       assert_eq!(state.cursor_position(), (INITIAL_CURSOR_POS_X+2, INITIAL_CURSOR_POS_Y+4));
-      assert_eq!(object_by_id(&state,1)?, Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y+1))));
+      assert_eq!(object_by_id(&state,1), Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y+1))));
       assert_eq!(state.selected_shape, Some(1));
       state.move_cursor(Direction::Left)?;
       assert_eq!(state.cursor_position(), (INITIAL_CURSOR_POS_X+1, INITIAL_CURSOR_POS_Y+4));
-      assert_eq!(object_by_id(&state,1)?, Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X-1,Y+1))));
+      assert_eq!(object_by_id(&state,1), Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X-1,Y+1))));
       assert_eq!(state.selected_shape, Some(1));
       state.move_cursor(Direction::Right)?;
       assert_eq!(state.cursor_position(), (INITIAL_CURSOR_POS_X+2, INITIAL_CURSOR_POS_Y+4));
-      assert_eq!(object_by_id(&state,1)?, Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y+1))));
+      assert_eq!(object_by_id(&state,1), Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y+1))));
       assert_eq!(state.selected_shape, Some(1));
       state.move_cursor(Direction::Up)?;
       assert_eq!(state.cursor_position(), (INITIAL_CURSOR_POS_X+2, INITIAL_CURSOR_POS_Y+3));
-      assert_eq!(object_by_id(&state,1)?, Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y))));
+      assert_eq!(object_by_id(&state,1), Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y))));
       assert_eq!(state.selected_shape, Some(1));
       state.move_cursor(Direction::Down)?;
       assert_eq!(state.cursor_position(), (INITIAL_CURSOR_POS_X+2, INITIAL_CURSOR_POS_Y+4));
-      assert_eq!(object_by_id(&state,1)?, Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y+1))));
+      assert_eq!(object_by_id(&state,1), Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y+1))));
       assert_eq!(state.selected_shape, Some(1));
       state.toggle_select_shape()?;
       assert_eq!(state.cursor_position(), (INITIAL_CURSOR_POS_X+2, INITIAL_CURSOR_POS_Y+4));
-      assert_eq!(object_by_id(&state,1)?, Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y+1))));
+      assert_eq!(object_by_id(&state,1), Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y+1))));
       assert_eq!(state.selected_shape, None);
       state.move_cursor(Direction::Right)?;
       assert_eq!(state.cursor_position(), (INITIAL_CURSOR_POS_X+3, INITIAL_CURSOR_POS_Y+4));
-      assert_eq!(object_by_id(&state,1)?, Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y+1))));
+      assert_eq!(object_by_id(&state,1), Some(Object::new(1, SHAPE, CONNECTORS, "None".to_string(), (X,Y+1))));
       assert_eq!(state.selected_shape, None);
       wait_for_dummy_thread(dummy_thread);
       Ok(())
     }
 
+    #[test]
+    /// ████
+    /// █╶x█
+    /// ████
+    fn parse_level_error_location() -> error::IOResult {
+      let (mut state, _, _) = State::new()?;
+      state.init_database()?;
+      match state.load_level("████\n█╶x█\n████".to_string()) {
+        Err(error::IOError::ParseLevelError(objects)) => assert_eq!(objects, vec![UnparsedObject { c: "x".to_string(), pos: (3,2), reason: "Unknown character".to_string() }]),
+        result                                        => panic!("Expected a parse error, but got {:?}", result)
+      }
+      Ok(())
+    }
+
+    #[test]
+    /// █████
+    /// █╶ ╴█
+    /// █████
+    fn hint_highlights_next_move() -> error::IOResult {
+      let (mut state, _, dummy_recv) = State::new()?;
+
+      let dummy_thread = thread::spawn(move || {
+        match (dummy_recv.recv(), dummy_recv.recv()) {
+          (Ok(StateControlPayload::PrintObjects(shape)), Ok(StateControlPayload::PrintObjects(target))) => {
+            assert_eq!(shape.len(), 1);
+            assert_eq!(target.len(), 1);
+            assert_eq!(shape[0].color(), Some(HINT_SHAPE_COLOR));
+            assert_eq!(target[0].color(), Some(HINT_TARGET_COLOR));
+            assert_eq!(target[0].pos(), (3,2));
+          },
+          payloads => panic!("Did not receive the hint: {:?}", payloads)
+        }
+      });
+
+      state.init_database()?;
+      state.load_level("█████\n█╶ ╴█\n█████".to_string())?;
+      state.hint()?;
+      assert!(state.hint_shown);
+      wait_for_dummy_thread(dummy_thread);
+      Ok(())
+    }
+
+    #[test]
+    /// ███████
+    /// █╶ █ ╴█
+    /// ███████
+    fn hint_not_found_shows_message() -> error::IOResult {
+      let (mut state, _, dummy_recv) = State::new()?;
+
+      let dummy_thread = thread::spawn(move || {
+        match dummy_recv.recv() {
+          Ok(StateControlPayload::Message(message, _)) => assert_eq!(message, "No hint, the board cannot be completed anymore"),
+          payload                                      => panic!("Did not receive the message: {:?}", payload)
+        }
+      });
+
+      state.init_database()?;
+      state.load_level("███████\n█╶ █ ╴█\n███████".to_string())?;
+      state.hint()?;
+      assert!(!state.hint_shown);
+      wait_for_dummy_thread(dummy_thread);
+      Ok(())
+    }
+
+    #[test]
+    /// ███████
+    /// █╶ █ ╴█
+    /// ███████
+    fn hint_not_found_shows_level_hint() -> error::IOResult {
+      let (mut state, _, dummy_recv) = State::new()?;
+
+      let dummy_thread = thread::spawn(move || {
+        match dummy_recv.recv() {
+          Ok(StateControlPayload::Message(message, _)) => assert_eq!(message, "No hint, the board cannot be completed anymore | Hint: Mind the wall"),
+          payload                                      => panic!("Did not receive the message: {:?}", payload)
+        }
+      });
+
+      state.init_database()?;
+      state.header = state.load_level("hint: Mind the wall\n---\n███████\n█╶ █ ╴█\n███████".to_string())?;
+      state.hint()?;
+      wait_for_dummy_thread(dummy_thread);
+      Ok(())
+    }
+
     #[test]
     /// ┌┐
     /// └┘
     fn simple_complete() -> error::IOResult {
-      let (state, _, _) = State::new()?;
+      let (mut state, _, _) = State::new()?;
       state.init_database()?;
-      add_object(&state, 1, 0b0110, "None".to_string(), 1, 1)?; // ┌
-      add_object(&state, 1, 0b0011, "None".to_string(), 2, 1)?; // ┐
-      add_object(&state, 1, 0b1001, "None".to_string(), 2, 2)?; // ┘
-      add_object(&state, 1, 0b1100, "None".to_string(), 1, 2)?; // └
-      assert_eq!(state.turn_state()?.1, Completeness::Complete);
+      add_object(&mut state, 1, 0b0110, "None".to_string(), 1, 1); // ┌
+      add_object(&mut state, 1, 0b0011, "None".to_string(), 2, 1); // ┐
+      add_object(&mut state, 1, 0b1001, "None".to_string(), 2, 2); // ┘
+      add_object(&mut state, 1, 0b1100, "None".to_string(), 1, 2); // └
+      assert_eq!(state.turn_state().1, Completeness::Complete);
       Ok(())
     }
 
@@ -1079,13 +1548,13 @@ mod tests {
     /// ┐┌
     /// └┘
     fn simple_single_shape_incomplete() -> error::IOResult {
-      let (state, _, _) = State::new()?;
+      let (mut state, _, _) = State::new()?;
       state.init_database()?;
-      add_object(&state, 1, 0b0110, "None".to_string(), 2, 1)?; // ┌
-      add_object(&state, 1, 0b0011, "None".to_string(), 1, 1)?; // ┐
-      add_object(&state, 1, 0b1001, "None".to_string(), 2, 2)?; // ┘
-      add_object(&state, 1, 0b1100, "None".to_string(), 1, 2)?; // └
-      assert_eq!(state.turn_state()?.1,Completeness::PartiallyComplete);
+      add_object(&mut state, 1, 0b0110, "None".to_string(), 2, 1); // ┌
+      add_object(&mut state, 1, 0b0011, "None".to_string(), 1, 1); // ┐
+      add_object(&mut state, 1, 0b1001, "None".to_string(), 2, 2); // ┘
+      add_object(&mut state, 1, 0b1100, "None".to_string(), 1, 2); // └
+      assert_eq!(state.turn_state().1,Completeness::PartiallyComplete);
       Ok(())
     }
 
@@ -1093,13 +1562,37 @@ mod tests {
     /// ┐ ┌
     /// └ ┘
     fn simple_two_shape_incomplete() -> error::IOResult {
-      let (state, _, _) = State::new()?;
+      let (mut state, _, _) = State::new()?;
       state.init_database()?;
-      add_object(&state, 2, 0b0110, "None".to_string(), 3, 1)?; // ┌
-      add_object(&state, 1, 0b0011, "None".to_string(), 1, 1)?; // ┐
-      add_object(&state, 2, 0b1001, "None".to_string(), 3, 2)?; // ┘
-      add_object(&state, 1, 0b1100, "None".to_string(), 1, 2)?; // └
-      assert_eq!(state.turn_state()?.1,Completeness::Incomplete);
+      add_object(&mut state, 2, 0b0110, "None".to_string(), 3, 1); // ┌
+      add_object(&mut state, 1, 0b0011, "None".to_string(), 1, 1); // ┐
+      add_object(&mut state, 2, 0b1001, "None".to_string(), 3, 2); // ┘
+      add_object(&mut state, 1, 0b1100, "None".to_string(), 1, 2); // └
+      assert_eq!(state.turn_state().1,Completeness::Incomplete);
+      Ok(())
+    }
+
+    #[test]
+    /// ╶ ╷
+    fn simple_unsolvable() -> error::IOResult {
+      let (mut state, _, _) = State::new()?;
+      state.init_database()?;
+      add_object(&mut state, 1, 0b0100, "None".to_string(), 1, 1); // ╶
+      add_object(&mut state, 2, 0b0010, "None".to_string(), 3, 1); // ╷
+      assert_eq!(state.turn_state().1, Completeness::Unsolvable);
+      Ok(())
+    }
+
+    #[test]
+    /// ╶ ╷
+    /// ◊
+    fn simple_unsolvable_with_volatile() -> error::IOResult {
+      let (mut state, _, _) = State::new()?;
+      state.init_database()?;
+      add_object(&mut state, 1, 0b0100, "None".to_string(),     1, 1); // ╶
+      add_object(&mut state, 2, 0b0010, "None".to_string(),     3, 1); // ╷
+      add_object(&mut state, 3, 0b0000, "Volatile".to_string(), 1, 2); // ◊
+      assert_eq!(state.turn_state().1, Completeness::Incomplete);
       Ok(())
     }
 
@@ -1107,17 +1600,61 @@ mod tests {
     /// ┌┐ ┌┐
     /// └┘ └┘
     fn simple_two_shape_complete() -> error::IOResult {
-      let (state, _, _) = State::new()?;
+      let (mut state, _, _) = State::new()?;
+      state.init_database()?;
+      add_object(&mut state, 1, 0b0110, "None".to_string(), 1, 1); // ┌
+      add_object(&mut state, 1, 0b0011, "None".to_string(), 2, 1); // ┐
+      add_object(&mut state, 1, 0b1001, "None".to_string(), 2, 2); // ┘
+      add_object(&mut state, 1, 0b1100, "None".to_string(), 1, 2); // └
+      add_object(&mut state, 2, 0b0110, "None".to_string(), 4, 1); // ┌
+      add_object(&mut state, 2, 0b0011, "None".to_string(), 5, 1); // ┐
+      add_object(&mut state, 2, 0b1001, "None".to_string(), 5, 2); // ┘
+      add_object(&mut state, 2, 0b1100, "None".to_string(), 4, 2); // └
+      assert_eq!(state.turn_state().1, Completeness::Complete);
+      Ok(())
+    }
+
+    #[test]
+    /// ╶─ ⊣▓     ╶─⊣
+    /// ▓███▓ -> ▓███
+    fn shapes_with_keys() -> error::IOResult {
+      let (mut state, _, dummy_recv) = State::new()?;
+      let dummy_thread = thread::spawn(move || while dummy_recv.recv().is_ok() {});
+
       state.init_database()?;
-      add_object(&state, 1, 0b0110, "None".to_string(), 1, 1)?; // ┌
-      add_object(&state, 1, 0b0011, "None".to_string(), 2, 1)?; // ┐
-      add_object(&state, 1, 0b1001, "None".to_string(), 2, 2)?; // ┘
-      add_object(&state, 1, 0b1100, "None".to_string(), 1, 2)?; // └
-      add_object(&state, 2, 0b0110, "None".to_string(), 4, 1)?; // ┌
-      add_object(&state, 2, 0b0011, "None".to_string(), 5, 1)?; // ┐
-      add_object(&state, 2, 0b1001, "None".to_string(), 5, 2)?; // ┘
-      add_object(&state, 2, 0b1100, "None".to_string(), 4, 2)?; // └
-      assert_eq!(state.turn_state()?.1, Completeness::Complete);
+      let mut shape: i32 = 1;
+      add_object(&mut state, shape, 0b00000100, "None".to_string(), 1, 1); // ╶
+      add_object(&mut state, shape, 0b00000101, "None".to_string(), 2, 1); // ─
+
+      shape += 1;
+      add_object(&mut state, shape, 0b00000001, "Key".to_string(), 4, 1); // ⊣
+
+      for (kind,x,y) in [("Lock",5,1),("Lock",5,2),("Lock",1,2),("None",2,2),("None",3,2),("None",4,2)] {
+        shape += 1;
+        add_object(&mut state, shape, 0, kind.to_string(), x, y); // ▓ or █
+      }
+
+      let (here,there,selected_object) = state.move_shape(1, (1,1), (2,1))?.expect("Shape returned `None`, where `Some` was expeced");
+      assert_eq!(here.len(), 2);
+      assert_eq!(there.len(), 5);
+      assert_eq!(selected_object, Some(1));
+
+      // The locks next to the key and next to them are removed, the lock elsewhere stays
+      assert_eq!(state.object_by_pos((4,1)), Some(Object::new(3, 1, 0b00000001, "Key".to_string(), (4,1))));
+      assert_eq!(state.object_by_pos((5,1)), None); // Removed
+      assert_eq!(state.object_by_pos((5,2)), None); // Removed
+      assert_eq!(state.object_by_pos((1,2)).map(|obj| obj.kind()), Some("Lock".to_string()));
+      assert_eq!(state.turn_state().1, Completeness::Complete);
+
+      state.undo()?;
+      assert_eq!(state.turn_state().0, 0);
+      assert_eq!(state.object_by_pos((5,1)).map(|obj| obj.kind()), Some("Lock".to_string()));
+      state.redo()?;
+      assert_eq!(state.turn_state().0, 1);
+      assert_eq!(state.object_by_pos((5,1)), None);
+
+      drop(state);
+      wait_for_dummy_thread(dummy_thread);
       Ok(())
     }
 
@@ -1126,40 +1663,37 @@ mod tests {
     /// │╞ ╡│ -> │╞═╡│ -> ││ ││
     /// └┘ └┘    └┘ └┘    └┘ └┘
     fn shapes_with_doors() -> error::IOResult {
-      let (state, _, _) = State::new()?;
+      let (mut state, _, _) = State::new()?;
       state.init_database()?;
-      let mut shape: i32 = state.db.query_row("select nextval('shape_seq_id')", params![], |row| row.get(0))?;
-      add_object(&state, shape, 0b00000110, "None".to_string(), 1, 1)?; // ┌
-      add_object(&state, shape, 0b00000011, "None".to_string(), 2, 1)?; // ┐
-      add_object(&state, shape, 0b00001001, "None".to_string(), 2, 3)?; // ┘
-      add_object(&state, shape, 0b00001100, "None".to_string(), 1, 3)?; // └
-      add_object(&state, shape, 0b00001010, "None".to_string(), 1, 2)?; // |
-      add_object(&state, shape, 0b01001010, "Door".to_string(), 2, 2)?; // ╞
-
-      shape = state.db.query_row("select nextval('shape_seq_id')", params![], |row| row.get(0))?;
-      add_object(&state, shape, 0b01010000, "Door".to_string(), 3, 1)?; // ═
-
-      shape = state.db.query_row("select nextval('shape_seq_id')", params![], |row| row.get(0))?;
-      add_object(&state, shape, 0b00000110, "None".to_string(), 4, 1)?; // ┌
-      add_object(&state, shape, 0b00000011, "None".to_string(), 5, 1)?; // ┐
-      add_object(&state, shape, 0b00001001, "None".to_string(), 5, 3)?; // ┘
-      add_object(&state, shape, 0b00001100, "None".to_string(), 4, 3)?; // └
-      add_object(&state, shape, 0b00001010, "None".to_string(), 5, 2)?; // |
-      add_object(&state, shape, 0b00011010, "Door".to_string(), 4, 2)?; // ╡
-
-      let mut db = state.db.try_clone()?;
-      let tx     = db.transaction()?;
-      let (here,there,selected_object) = State::move_shape(&tx, 2, (3,1), (3,2), (10,10), UNDO_SIZE_IN_TURNS)?.expect("Shape returned `None`, where `Some` was expeced");
+      let mut shape: i32 = 1;
+      add_object(&mut state, shape, 0b00000110, "None".to_string(), 1, 1); // ┌
+      add_object(&mut state, shape, 0b00000011, "None".to_string(), 2, 1); // ┐
+      add_object(&mut state, shape, 0b00001001, "None".to_string(), 2, 3); // ┘
+      add_object(&mut state, shape, 0b00001100, "None".to_string(), 1, 3); // └
+      add_object(&mut state, shape, 0b00001010, "None".to_string(), 1, 2); // |
+      add_object(&mut state, shape, 0b01001010, "Door".to_string(), 2, 2); // ╞
+
+      shape += 1;
+      add_object(&mut state, shape, 0b01010000, "Door".to_string(), 3, 1); // ═
+
+      shape += 1;
+      add_object(&mut state, shape, 0b00000110, "None".to_string(), 4, 1); // ┌
+      add_object(&mut state, shape, 0b00000011, "None".to_string(), 5, 1); // ┐
+      add_object(&mut state, shape, 0b00001001, "None".to_string(), 5, 3); // ┘
+      add_object(&mut state, shape, 0b00001100, "None".to_string(), 4, 3); // └
+      add_object(&mut state, shape, 0b00001010, "None".to_string(), 5, 2); // |
+      add_object(&mut state, shape, 0b00011010, "Door".to_string(), 4, 2); // ╡
+
+      let (here,there,selected_object) = state.move_shape(2, (3,1), (3,2))?.expect("Shape returned `None`, where `Some` was expeced");
       assert_eq!(here.len(), 1);
       assert_eq!(there.len(), 13);
       assert_eq!(selected_object, None);
-      tx.commit()?;
 
-      assert_eq!(state.object_by_pos((3,2))?, None); // Removed
-      assert_eq!(state.object_by_pos((2,2))?, Some(Object::new( 6, shape+1, 0b00001010, "None".to_string(), (2,2)))); // ╞ → |
-      assert_eq!(state.object_by_pos((4,2))?, Some(Object::new(13, shape+2, 0b00001010, "None".to_string(), (4,2)))); // ╡ → |
-      assert_eq!(state.db.query_row("select count(distinct o.shape) from objects as o", params![], |row| row.get(0)), Ok(2));
-      assert_eq!(state.turn_state()?.1, Completeness::Complete);
+      assert_eq!(state.object_by_pos((3,2)), None); // Removed
+      assert_eq!(state.object_by_pos((2,2)), Some(Object::new( 6, shape+1, 0b00001010, "None".to_string(), (2,2)))); // ╞ → |
+      assert_eq!(state.object_by_pos((4,2)), Some(Object::new(13, shape+2, 0b00001010, "None".to_string(), (4,2)))); // ╡ → |
+      assert_eq!(state.board.parts().map(|part| part.shape).collect::<HashSet<i32>>().len(), 2);
+      assert_eq!(state.turn_state().1, Completeness::Complete);
 
       Ok(())
     }
@@ -1184,65 +1718,320 @@ mod tests {
       });
 
       state.init_database()?;
-      let mut shape: i32 = state.db.query_row("select nextval('shape_seq_id')", params![], |row| row.get(0))?;
-      add_object(&state, shape, 0b00000110, "None".to_string(), 1, 1)?; // ┌
-      add_object(&state, shape, 0b00000011, "None".to_string(), 2, 1)?; // ┐
-      add_object(&state, shape, 0b00001001, "None".to_string(), 2, 3)?; // ┘
-      add_object(&state, shape, 0b00001100, "None".to_string(), 1, 3)?; // └
-      add_object(&state, shape, 0b00001010, "None".to_string(), 1, 2)?; // |
-      add_object(&state, shape, 0b01001010, "Door".to_string(), 2, 2)?; // ╞
-      add_object(&state, shape, 0b01010000, "Door".to_string(), 3, 2)?; // ═
-
-      shape = state.db.query_row("select nextval('shape_seq_id')", params![], |row| row.get(0))?;
-      add_object(&state, shape, 0b00000110, "None".to_string(), 4, 2)?; // ┌
-      add_object(&state, shape, 0b00000011, "None".to_string(), 5, 2)?; // ┐
-      add_object(&state, shape, 0b00001001, "None".to_string(), 5, 4)?; // ┘
-      add_object(&state, shape, 0b00001100, "None".to_string(), 4, 4)?; // └
-      add_object(&state, shape, 0b00001010, "None".to_string(), 5, 3)?; // |
-      add_object(&state, shape, 0b00011010, "Door".to_string(), 4, 3)?; // ╡
-
-      let mut db = state.db.try_clone()?;
-      let tx     = db.transaction()?;
-      let (here,there,selected_object) = State::move_shape(&tx, 2, (4,2), (4,1), (10,10), UNDO_SIZE_IN_TURNS)?.expect("Shape returned `None`, where `Some` was expeced");
+      let mut shape: i32 = 1;
+      add_object(&mut state, shape, 0b00000110, "None".to_string(), 1, 1); // ┌
+      add_object(&mut state, shape, 0b00000011, "None".to_string(), 2, 1); // ┐
+      add_object(&mut state, shape, 0b00001001, "None".to_string(), 2, 3); // ┘
+      add_object(&mut state, shape, 0b00001100, "None".to_string(), 1, 3); // └
+      add_object(&mut state, shape, 0b00001010, "None".to_string(), 1, 2); // |
+      add_object(&mut state, shape, 0b01001010, "Door".to_string(), 2, 2); // ╞
+      add_object(&mut state, shape, 0b01010000, "Door".to_string(), 3, 2); // ═
+
+      shape += 1;
+      add_object(&mut state, shape, 0b00000110, "None".to_string(), 4, 2); // ┌
+      add_object(&mut state, shape, 0b00000011, "None".to_string(), 5, 2); // ┐
+      add_object(&mut state, shape, 0b00001001, "None".to_string(), 5, 4); // ┘
+      add_object(&mut state, shape, 0b00001100, "None".to_string(), 4, 4); // └
+      add_object(&mut state, shape, 0b00001010, "None".to_string(), 5, 3); // |
+      add_object(&mut state, shape, 0b00011010, "Door".to_string(), 4, 3); // ╡
+
+      let (here,there,selected_object) = state.move_shape(2, (4,2), (4,1))?.expect("Shape returned `None`, where `Some` was expeced");
       assert_eq!(here.len(), 6);
       assert_eq!(there.len(), 13);
       assert_eq!(selected_object, Some(4));
-      tx.commit()?;
 
-      assert_eq!(state.object_by_pos((3,2))?, None); // Removed
-      assert_eq!(state.object_by_pos((2,2))?, Some(Object::new( 6, shape+1, 0b00001010, "None".to_string(), (2,2)))); // ╞ → |
-      assert_eq!(state.object_by_pos((4,2))?, Some(Object::new(13, shape+2, 0b00001010, "None".to_string(), (4,2)))); // ╡ → |
-      assert_eq!(state.db.query_row("select count(distinct o.shape) from objects as o", params![], |row| row.get(0)), Ok(2));
-      assert_eq!(state.turn_state()?.1, Completeness::Complete);
+      assert_eq!(state.object_by_pos((3,2)), None); // Removed
+      assert_eq!(state.object_by_pos((2,2)), Some(Object::new( 6, shape+1, 0b00001010, "None".to_string(), (2,2)))); // ╞ → |
+      assert_eq!(state.object_by_pos((4,2)), Some(Object::new(13, shape+2, 0b00001010, "None".to_string(), (4,2)))); // ╡ → |
+      assert_eq!(state.board.parts().map(|part| part.shape).collect::<HashSet<i32>>().len(), 2);
+      assert_eq!(state.turn_state().1, Completeness::Complete);
 
       let door_object = Some(Object::new_with_color(7, 1, 0b01010000, "Door".to_string(), (3,2), None));
-      fn query_object(state: &State, query: &str) -> duckdb::Result<Option<Object>> {
-        state.db.query_row(query,
-                           params![],
-                           |row| Ok(Object::new_with_color(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, (row.get(4)?, row.get(5)?), None))).optional()
+      // Only the moved, merged and removed objects are stored for the turn
+      fn query_removed(state: &State) -> duckdb::Result<Option<Object>> {
+        state.db.query_row(r#"
+          select c.object_id, c.before_shape, c.before_connectors, c.before_kind::text, c.before_x, c.before_y
+          from   changes as c
+          where  c.after_shape is null
+        "#, params![], |row| Ok(Object::new_with_color(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, (row.get(4)?, row.get(5)?), None))).optional()
       }
 
-      fn query_from_undo(state: &State) -> duckdb::Result<Option<Object>> {
-        query_object(&state, "select u.object_id, u.shape, u.connectors, u.kind::text, u.x, u.y from undo as u where (u.x,u.y) = (3,2)")
-      }
+      assert_eq!(query_removed(&state)?, door_object);
+      assert_eq!(state.db.query_row("select count(*) from changes as c", params![], |row| row.get(0)), Ok(13));
+      assert_eq!(state.object_by_pos((3,2)), None);
+      state.undo()?;
+      assert_eq!(state.turn_state().0, 0);
+      assert_eq!(state.object_by_pos((3,2)), door_object);
+      state.redo()?;
+      assert_eq!(state.turn_state().0, 1);
+      assert_eq!(state.object_by_pos((3,2)), None);
+      assert_eq!(query_removed(&state)?, door_object);
+
+      wait_for_dummy_thread(dummy_thread);
+      Ok(())
+    }
+
+    #[test]
+    /// ██████    ██████
+    /// █╶╎╴█ -> █╶╌╴█
+    /// ██████    ██████
+    fn rotate_rotors() -> error::IOResult {
+      let (mut state, _, dummy_recv) = State::new()?;
+      let dummy_thread = thread::spawn(move || while dummy_recv.recv().is_ok() {});
+
+      state.init_database()?;
+      state.load_level("██████\n█╶╎╴█\n██████".to_string())?;
+      state.cursor_pos = (3,2);
+      state.toggle_select_shape()?;
+      state.rotate()?;
+      assert_eq!(state.object_by_pos((3,2)).map(|obj| (obj.connectors, obj.kind())), Some((0b0101, "Rotor".to_string())));
+      assert_eq!(state.selected_shape, state.object_by_pos((2,2)).map(|obj| obj.shape));
+      assert_eq!(state.turn_state(), (1, Completeness::Complete));
+      state.undo()?;
+      assert_eq!(state.object_by_pos((3,2)).map(|obj| obj.connectors), Some(0b1010));
+      assert_eq!(state.turn_state(), (0, Completeness::Incomplete));
+
+      drop(state);
+      wait_for_dummy_thread(dummy_thread);
+      Ok(())
+    }
 
-      fn query_from_redo(state: &State) -> duckdb::Result<Option<Object>> {
-        query_object(&state, "select r.object_id, r.shape, r.connectors, r.kind::text, r.x, r.y from redo as r where (r.x,r.y) = (3,2)")
+    #[test]
+    /// █████████    █████████
+    /// █╶① █ ①╴█ -> █ ① █ ╶╴█
+    /// █████████    █████████
+    fn teleport_and_undo() -> error::IOResult {
+      let (mut state, _, dummy_recv) = State::new()?;
+      let dummy_thread = thread::spawn(move || while dummy_recv.recv().is_ok() {});
+
+      let level = "█████████\n█╶① █ ①╴█\n█████████";
+      state.init_database()?;
+      state.load_level(level.to_string())?;
+      assert_eq!(state.board_to_level_string(), level);
+      state.set_board_size((9,3))?;
+      state.cursor_pos = (2,2);
+      state.toggle_select_shape()?;
+      state.move_cursor(Direction::Right)?;
+      // The cursor follows the shape through the teleporter, which stays beneath it
+      assert_eq!(state.cursor_position(), (7,2));
+      assert_eq!(state.object_by_pos((7,2)).map(|obj| obj.connectors), Some(0b0100));
+      assert_eq!(state.object_by_pos((3,2)).map(|obj| obj.kind()), Some("Teleporter".to_string()));
+      assert_eq!(state.turn_state(), (1, Completeness::Complete));
+      state.undo()?;
+      assert_eq!(state.object_by_pos((2,2)).map(|obj| obj.connectors), Some(0b0100));
+      assert_eq!(state.object_by_pos((7,2)).map(|obj| obj.kind()), Some("Teleporter".to_string()));
+      assert_eq!(state.turn_state(), (0, Completeness::Incomplete));
+
+      drop(state);
+      wait_for_dummy_thread(dummy_thread);
+      Ok(())
+    }
+
+    #[test]
+    /// ████████    ████████
+    /// █╶░░░ ╴█ -> █ ░░░╶╴█
+    /// ████████    ████████
+    fn slide_on_ice_in_one_turn() -> error::IOResult {
+      let (mut state, _, dummy_recv) = State::new()?;
+
+      state.init_database()?;
+      state.load_level("████████\n█╶░░░ ╴█\n████████".to_string())?;
+      state.board_size = (8,3);
+      state.cursor_pos = (2,2);
+      state.toggle_select_shape()?;
+      state.move_cursor(Direction::Right)?;
+      let moves = dummy_recv.try_iter().filter_map(|payload| match payload {
+        StateControlPayload::MoveShape(here, there) => Some((here.iter().map(|obj| obj.pos).collect::<Vec<_>>(), there.iter().map(|obj| obj.pos).collect::<Vec<_>>())),
+        _                                           => None
+      }).collect::<Vec<_>>();
+      assert_eq!(moves, vec![(vec![(2,2)], vec![(6,2),(7,2)])]);
+      assert_eq!(state.cursor_position(), (6,2));
+      assert_eq!(state.turn_state(), (1, Completeness::Complete));
+      state.undo()?;
+      assert_eq!(state.object_by_pos((2,2)).map(|obj| obj.connectors), Some(0b0100));
+      assert_eq!(state.object_by_pos((6,2)), None);
+      assert_eq!(state.turn_state(), (0, Completeness::Incomplete));
+      Ok(())
+    }
+
+    #[test]
+    /// ██████
+    /// █╶   █
+    /// █   ╴█
+    /// ██████
+    fn branching_history() -> error::IOResult {
+      let (mut state, _, dummy_recv) = State::new()?;
+      let dummy_thread = thread::spawn(move || while dummy_recv.recv().is_ok() {});
+
+      state.init_database()?;
+      state.load_level("██████\n█╶   █\n█   ╴█\n██████".to_string())?;
+      fn move_shape_at(state: &mut State, here: (u16,u16), there: (u16,u16)) -> error::IOResult {
+        let shape = state.object_by_pos(here).expect("No object to move").shape;
+        state.move_shape(shape, here, there)?;
+        Ok(())
       }
 
-      assert_eq!(query_from_undo(&state)?, door_object);
-      assert_eq!(state.object_by_pos((3,2))?, None);
-      assert_eq!(query_from_redo(&state)?, None);
+      move_shape_at(&mut state, (2,2), (3,2))?;
       state.undo()?;
-      assert_eq!(query_from_undo(&state)?, None);
-      assert_eq!(state.object_by_pos((3,2))?, door_object);
-      assert_eq!(query_from_redo(&state)?, None);
+      move_shape_at(&mut state, (2,2), (2,3))?;
+      // Moving after undoing keeps the first move as another branch
+      assert_eq!(state.turn_state().0, 1);
+      assert_eq!(state.branches()?.iter().map(|branch| (branch.turn, branch.current)).collect::<Vec<_>>(), vec![(1,false), (1,true)]);
+      state.switch_branch(false)?;
+      assert!(state.object_by_pos((3,2)).is_some());
+      assert_eq!(state.branches()?.iter().map(|branch| branch.current).collect::<Vec<_>>(), vec![true, false]);
+      state.switch_branch(false)?;
+      assert!(state.object_by_pos((3,2)).is_some());
+      // Redo follows the last visited branch
+      state.undo()?;
+      assert!(state.object_by_pos((2,2)).is_some());
       state.redo()?;
-      assert_eq!(query_from_undo(&state)?, door_object);
-      assert_eq!(state.object_by_pos((3,2))?, None);
-      assert_eq!(query_from_redo(&state)?, None);
+      assert!(state.object_by_pos((3,2)).is_some());
+      state.switch_branch(true)?;
+      assert!(state.object_by_pos((2,3)).is_some());
 
-      wait_for_dummy_thread(dummy_thread);
+      drop(state);
+      assert!(dummy_thread.join().is_ok());
       Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    /// ██████
+    /// █╶   █
+    /// █   ╴█
+    /// ██████
+    fn goto_turn_in_timeline() -> error::IOResult {
+      let (mut state, _, dummy_recv) = State::new()?;
+      let dummy_thread = thread::spawn(move || while dummy_recv.recv().is_ok() {});
+
+      state.init_database()?;
+      state.load_level("██████\n█╶   █\n█   ╴█\n██████".to_string())?;
+      for (here, there) in [((2,2),(3,2)), ((3,2),(4,2)), ((4,2),(4,3))] {
+        let shape = state.object_by_pos(here).expect("No object to move").shape;
+        state.move_shape(shape, here, there)?;
+      }
+      // Each turn stores only the objects it changed, not the walls
+      assert_eq!(state.db.query_row("select count(*) from changes as c", params![], |row| row.get(0)), Ok(4));
+      state.goto_turn(1)?;
+      assert_eq!(state.turn_state().0, 1);
+      assert!(state.object_by_pos((3,2)).is_some());
+      let timeline = state.timeline()?;
+      assert_eq!(timeline.iter().map(|turn| (turn.turn, turn.direction, turn.moved.len(), turn.current)).collect::<Vec<_>>(),
+                 vec![(0, None, 0, false), (1, Some(Direction::Right), 1, true), (2, Some(Direction::Right), 1, false), (3, Some(Direction::Down), 1, false)]);
+      // Turns after the last one go to the last one
+      state.goto_turn(i32::MAX)?;
+      assert!(state.object_by_pos((4,3)).is_some());
+
+      drop(state);
+      assert!(dummy_thread.join().is_ok());
+      Ok(())
+    }
+
+    #[test]
+    /// █████    █████
+    /// █╶ ╴█ -> █ ╶╴█
+    /// █████    █████
+    fn history_keeps_completeness_of_turns() -> error::IOResult {
+      let (mut state, _, dummy_recv) = State::new()?;
+      let dummy_thread = thread::spawn(move || while dummy_recv.recv().is_ok() {});
+
+      state.init_database()?;
+      state.load_level("█████\n█╶ ╴█\n█████".to_string())?;
+      state.start_history()?;
+      let shape = state.object_by_pos((2,2)).expect("No object to move").shape;
+      state.move_shape(shape, (2,2), (3,2))?;
+      assert_eq!(state.timeline()?.iter().map(|turn| turn.completeness).collect::<Vec<_>>(), vec![Some(Completeness::Incomplete), Some(Completeness::Complete)]);
+      // Printing the turn counter leaves the history as it is
+      state.db.execute("update history set completeness = null", params![])?;
+      state.print_turn_counter()?;
+      assert!(state.timeline()?.iter().all(|turn| turn.completeness.is_none()));
+
+      drop(state);
+      assert!(dummy_thread.join().is_ok());
+      Ok(())
+    }
+
+    #[test]
+    // Save and load through unique temporary directories, a corrupt or incompatible save leaves the board as it is
+    fn save_and_load_archive() -> error::IOResult {
+      let (mut state, _, dummy_recv) = State::new()?;
+      let dummy_thread = thread::spawn(move || while dummy_recv.recv().is_ok() {});
+
+      state.init_database()?;
+      state.load_level("██████\n█╶   █\n█   ╴█\n██████".to_string())?;
+      let save_directory = archive::TempDirectory::new("test")?;
+      let save_path      = save_directory.path().join("test.sav").to_string_lossy().to_string();
+      state.save_archive(save_path.as_str())?;
+      let shape = state.object_by_pos((2,2)).expect("No object to move").shape;
+      state.move_shape(shape, (2,2), (3,2))?;
+      // Saving again replaces the save, without leaving anything else behind
+      state.save_archive(save_path.as_str())?;
+      assert_eq!(fs::read_dir(save_directory.path())?.count(), 1);
+      state.goto_turn(0)?;
+      state.load(save_path.as_str())?;
+      assert_eq!(state.turn_state().0, 1);
+      assert!(state.object_by_pos((3,2)).is_some());
+      fs::write(save_path.as_str(), "not a zip archive")?;
+      state.load(save_path.as_str())?;
+      assert_eq!(state.turn_state().0, 1);
+      assert!(state.object_by_pos((3,2)).is_some());
+      // Saves of another version of the level are refused
+      state.level_checksum = Some(archive::level_checksum("██████\n█╶   █\n█   ╴█\n██████"));
+      state.save_archive(save_path.as_str())?;
+      state.level_checksum = Some(archive::level_checksum("██████\n█╶  ╴█\n██████"));
+      state.goto_turn(0)?;
+      state.load(save_path.as_str())?;
+      assert_eq!(state.turn_state().0, 0);
+
+      drop(state);
+      assert!(dummy_thread.join().is_ok());
+      Ok(())
+    }
+
+    // Connectors, kind and position of an object
+    type ShapeObject = (i32,String,u16,u16);
+
+    // Helper function that lists the objects of every shape, to compare boards regardless of ids
+    fn shapes(state: &State) -> Vec<Vec<ShapeObject>> {
+      let mut shapes: HashMap<i32,Vec<ShapeObject>> = HashMap::new();
+      for part in state.board.parts().filter(|part| part.connectors > 0) {
+        shapes.entry(part.shape).or_default().push((part.connectors, part.kind.to_string(), part.pos.0, part.pos.1));
+      }
+      let mut shapes: Vec<Vec<ShapeObject>> = shapes.into_values().map(|mut objects| { objects.sort(); objects }).collect();
+      shapes.sort();
+      shapes
+    }
+
+    #[test]
+    // Exporting the board of a level gives back the level, every character maps back onto itself
+    fn export_level_round_trip() -> error::IOResult {
+      let level = "███████████\n█┝━┥ ┠  ◊ █\n█╞═╡ ┃ ┌─┐█\n█ ┌┐ ┸ ╟ │█\n█ └┘   ╙─┘█\n███████████";
+      let (mut state, _, _) = State::new()?;
+      state.init_database()?;
+      state.load_level(level.to_string())?;
+      assert_eq!(state.board_to_level_string(), level);
+      Ok(())
+    }
+
+    #[test]
+    // A board exported after a move parses into the same shapes
+    fn export_moved_board() -> error::IOResult {
+      let (mut state, _, _) = State::new()?;
+      state.init_database()?;
+      state.load_level("████████\n█┌┐ ┌┐ █\n█└┘ ╶┘ █\n█     ◊█\n████████".to_string())?;
+      let shape = state.object_by_pos((2,2)).expect("No object to move").shape;
+      state.move_shape(shape, (2,2), (2,3))?;
+      let (mut exported, _, _) = State::new()?;
+      exported.init_database()?;
+      exported.load_level(state.board_to_level_string())?;
+      assert_eq!(shapes(&exported), shapes(&state));
+      assert_eq!(exported.board_to_level_string(), state.board_to_level_string());
+      Ok(())
+    }
+
+    #[test]
+    fn autosave_file_path() {
+      assert_eq!(State::get_autosave_file_path("levels/01-first.lvl"), "levels/01-first.autosave");
+      assert_eq!(State::get_autosave_file_path("my.lvl.d/01-first.lvl"), "my.lvl.d/01-first.autosave");
+    }
+}
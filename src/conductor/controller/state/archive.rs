@@ -0,0 +1,250 @@
+use std::{env, fs, io, path::{Path, PathBuf}, process, sync::atomic::{AtomicUsize, Ordering}, time::{SystemTime, UNIX_EPOCH}};
+use zip_archive::Archiver;
+
+use super::error;
+
+/// Directory inside every save archive holding the exported database
+pub const EXPORT_DIRECTORY   : &str = "temp-save";
+/// Format of the saves written, saves without a version file are of format 1
+pub const SAVE_FORMAT_VERSION: u32  = 2;
+const LOAD_FILE              : &str = "load.sql";
+const VERSION_FILE           : &str = "version";
+const SESSION_FILE           : &str = "session";
+const TEMP_DIRECTORY_PREFIX  : &str = "connect";
+/// Tables of the history since format 1 stores only the changes of every turn
+const HISTORY_TABLES         : [&str; 3] = ["history", "changes", "head"];
+const FNV_OFFSET_BASIS       : u64  = 0xcbf29ce484222325;
+const FNV_PRIME              : u64  = 0x100000001b3;
+
+static TEMP_DIRECTORY_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// A directory of its own in the temporary directory of the system, removed with everything in it once dropped
+pub struct TempDirectory {
+  path: PathBuf
+}
+
+impl TempDirectory {
+  /// Unique across processes by the process id and within a process by a counter
+  pub fn new(purpose: &str) -> io::Result<Self> {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.subsec_nanos());
+    let path  = env::temp_dir().join(format!("{}-{}-{}-{}-{}", TEMP_DIRECTORY_PREFIX, purpose, process::id(), TEMP_DIRECTORY_COUNT.fetch_add(1, Ordering::Relaxed), nanos));
+    fs::create_dir_all(&path)?;
+    Ok(Self { path })
+  }
+
+  #[inline]
+  pub fn path(&self) -> &Path { &self.path }
+}
+
+impl Drop for TempDirectory {
+  fn drop(&mut self) {
+    if let Err(e) = fs::remove_dir_all(&self.path) {
+      log::warn!("Could not remove temporary directory {}: {}", self.path.display(), e);
+    }
+  }
+}
+
+/// FNV-1a hash of the level file, telling saves of different versions of a level apart
+pub fn level_checksum(level_string: &str) -> u64 {
+  level_string.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME))
+}
+
+/// Format of a save and checksum of the level it was saved for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SaveVersion {
+  pub format        : u32,
+  pub level_checksum: Option<u64>
+}
+
+impl SaveVersion {
+  #[inline]
+  pub fn current(level_checksum: Option<u64>) -> Self { Self { format: SAVE_FORMAT_VERSION, level_checksum } }
+
+  /// Every line of the version file holds a `key: value` pair, unknown keys are skipped
+  fn parse(version_string: &str) -> Option<Self> {
+    let (mut format, mut level_checksum) = (None, None);
+    for (key, value) in version_string.lines().filter_map(|line| line.split_once(':')) {
+      let value = value.trim();
+      match key.trim() {
+        "format" => format         = Some(value.parse().ok()?),
+        "level"  => level_checksum = Some(u64::from_str_radix(value, 16).ok()?),
+        _        => ()
+      }
+    }
+    Some(Self { format: format?, level_checksum })
+  }
+
+  fn to_version_string(self) -> String {
+    format!("format: {}\n{}", self.format, self.level_checksum.map_or(String::new(), |checksum| format!("level: {:016x}\n", checksum)))
+  }
+
+  /// Write the version file into the export directory
+  pub fn write(self, export_directory: &Path) -> io::Result<()> { fs::write(export_directory.join(VERSION_FILE), self.to_version_string()) }
+
+  /// Refuse saves of a newer format or of another version of the level with `level_checksum`,
+  /// otherwise bring the load statements of older formats up to the current one
+  pub fn migrate(self, load_string: String, level_checksum: Option<u64>) -> Result<String, String> {
+    if self.format > SAVE_FORMAT_VERSION {
+      return Err(format!("Saved in format {}, but only formats up to {} are supported, update connect to load it", self.format, SAVE_FORMAT_VERSION))
+    }
+    if let (Some(saved_checksum), Some(level_checksum)) = (self.level_checksum, level_checksum) {
+      if saved_checksum != level_checksum {
+        return Err("Saved for another version of the level, the level file changed since".to_string())
+      }
+    }
+    match self.format {
+      // Format 1 saves may hold the undo and redo tables or snapshots of earlier histories,
+      // keep their history only, if it is the current one, otherwise load just the board
+      1 => {
+        let copied_table = |line: &str| line.strip_prefix("COPY ").and_then(|line| line.split_whitespace().next()).map(str::to_string);
+        let tables: Vec<String> = load_string.lines().filter_map(copied_table).collect();
+        let history_kept = HISTORY_TABLES.iter().all(|table| tables.iter().any(|copied| copied == table));
+        Ok(load_string.lines()
+          .filter(|line| copied_table(line).is_none_or(|table| table == "objects" || (history_kept && HISTORY_TABLES.contains(&table.as_str()))))
+          .map(|line| format!("{}\n", line))
+          .collect())
+      },
+      _ => Ok(load_string)
+    }
+  }
+}
+
+/// Cursor position and selected shape at the time of saving, which are not part of the database
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Session {
+  pub cursor_pos    : (u16,u16),
+  pub selected_shape: Option<i32>
+}
+
+impl Session {
+  /// Every line of the session file holds a `key: value` pair, unknown keys are skipped
+  fn parse(session_string: &str) -> Option<Self> {
+    let (mut cursor_pos, mut selected_shape) = (None, None);
+    for (key, value) in session_string.lines().filter_map(|line| line.split_once(':')) {
+      let value = value.trim();
+      match key.trim() {
+        "cursor"   => cursor_pos     = value.split_once(' ').and_then(|(x, y)| x.parse().ok().zip(y.parse().ok())),
+        "selected" => selected_shape = Some(value.parse().ok()?),
+        _          => ()
+      }
+    }
+    Some(Self { cursor_pos: cursor_pos?, selected_shape })
+  }
+
+  fn to_session_string(self) -> String {
+    format!("cursor: {} {}\n{}", self.cursor_pos.0, self.cursor_pos.1, self.selected_shape.map_or(String::new(), |shape| format!("selected: {}\n", shape)))
+  }
+
+  /// Write the session file into the export directory
+  pub fn write(self, export_directory: &Path) -> io::Result<()> { fs::write(export_directory.join(SESSION_FILE), self.to_session_string()) }
+}
+
+/// Quote a path as string literal in SQL
+#[inline]
+pub fn sql_path(path: &Path) -> String { format!("'{}'", path.to_string_lossy().replace('\'', "''")) }
+
+/// Zip `export_directory` into an archive in `archive_directory` and return its path
+pub fn archive(export_directory: &Path, archive_directory: &Path) -> Result<PathBuf, error::IOError> {
+  let mut archiver = Archiver::new();
+  archiver.push(export_directory);
+  archiver.set_destination(archive_directory);
+  archiver.archive().map_err(|e| error::IOError::SaveArchiveError(export_directory.display().to_string(), e.to_string()))?;
+  let archive_path = archive_directory.join(format!("{}.zip", export_directory.file_name().map_or(EXPORT_DIRECTORY.into(), |name| name.to_string_lossy())));
+  if !archive_path.is_file() {
+    return Err(error::IOError::SaveArchiveError(archive_path.display().to_string(), "Archive was not written".to_string()))
+  }
+  Ok(archive_path)
+}
+
+/// Extract the archive at `archive_path` into `directory` and return its version, its session, if saved, and its load statements,
+/// pointing at the extracted files instead of the directory they were exported to
+pub fn extract(archive_path: &Path, directory: &Path) -> Result<(SaveVersion, Option<Session>, String), error::IOError> {
+  let archive_error = |msg: String| error::IOError::SaveArchiveError(archive_path.display().to_string(), msg);
+  zip_extract::extract(fs::File::open(archive_path)?, directory, false).map_err(|e| archive_error(e.to_string()))?;
+  let export_directory = directory.join(EXPORT_DIRECTORY);
+  let load_string      = fs::read_to_string(export_directory.join(LOAD_FILE)).map_err(|e| archive_error(format!("Cannot read {}: {}", LOAD_FILE, e)))?;
+  let version          = match fs::read_to_string(export_directory.join(VERSION_FILE)) {
+    Ok(version_string)                            => SaveVersion::parse(version_string.as_str()).ok_or_else(|| archive_error(format!("Cannot read {}", VERSION_FILE)))?,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => SaveVersion { format: 1, level_checksum: None },
+    Err(e)                                        => Err(archive_error(format!("Cannot read {}: {}", VERSION_FILE, e)))?
+  };
+  let session          = fs::read_to_string(export_directory.join(SESSION_FILE)).ok().and_then(|session_string| Session::parse(session_string.as_str()));
+  Ok((version, session, relocate_load_string(load_string.as_str(), export_directory.as_path())))
+}
+
+/// Every `COPY <table> FROM '<path>' ...` statement reads the file of the same name in `export_directory` instead
+fn relocate_load_string(load_string: &str, export_directory: &Path) -> String {
+  load_string.lines().map(|line| match line.split_once(" FROM '").and_then(|(copy, rest)| rest.split_once("' ").map(|(path, options)| (copy, path, options))) {
+    Some((copy, path, options)) => format!("{} FROM {} {}\n", copy, sql_path(export_directory.join(Path::new(path).file_name().unwrap_or_default()).as_path()), options),
+    None                        => format!("{}\n", line)
+  }).collect()
+}
+
+/// Copy the archive next to `save_path`, write it to disk and rename it to `save_path`,
+/// so a crash leaves either the previous or the new save, never a partial one
+pub fn replace_atomically(archive_path: &Path, save_path: &Path) -> io::Result<()> {
+  let temp_path = PathBuf::from(format!("{}.{}.tmp", save_path.display(), process::id()));
+  let replaced  = fs::copy(archive_path, &temp_path)
+    .and_then(|_| fs::OpenOptions::new().write(true).open(&temp_path)?.sync_all())
+    .and_then(|_| fs::rename(&temp_path, save_path));
+  if replaced.is_err() && temp_path.exists() {
+    fs::remove_file(&temp_path)?;
+  }
+  replaced?;
+  // Write the directory entry of the renamed file to disk as well
+  #[cfg(unix)]
+  fs::File::open(save_path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new(".")))?.sync_all()?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relocate_load_statements() {
+      let load_string = "COPY objects FROM 'temp-save/objects.csv' (FORMAT 'csv', header 1);\n\nCOPY head FROM '/tmp/connect-save-1/temp-save/head.csv' (FORMAT 'csv');\n";
+      assert_eq!(relocate_load_string(load_string, Path::new("/tmp/it's here")),
+                 "COPY objects FROM '/tmp/it''s here/objects.csv' (FORMAT 'csv', header 1);\n\nCOPY head FROM '/tmp/it''s here/head.csv' (FORMAT 'csv');\n");
+    }
+
+    #[test]
+    fn version_round_trip() {
+      let version = SaveVersion::current(Some(level_checksum("██████\n█┌  ┐█\n██████")));
+      assert_eq!(SaveVersion::parse(version.to_version_string().as_str()), Some(version));
+      assert_eq!(SaveVersion::parse("format: 1"), Some(SaveVersion { format: 1, level_checksum: None }));
+      assert_eq!(SaveVersion::parse("level: 0123"), None);
+      assert_ne!(level_checksum("█┌┐█"), level_checksum("█┌┘█"));
+    }
+
+    #[test]
+    fn session_round_trip() {
+      for session in [Session { cursor_pos: (3,2), selected_shape: Some(7) }, Session { cursor_pos: (0,0), selected_shape: None }] {
+        assert_eq!(Session::parse(session.to_session_string().as_str()), Some(session));
+      }
+      assert_eq!(Session::parse("selected: 7"), None);
+    }
+
+    #[test]
+    fn migrate_saves() {
+      let undo_redo = "COPY objects FROM 'a/objects.csv' (FORMAT 'csv');\nCOPY undo FROM 'a/undo.csv' (FORMAT 'csv');\nCOPY redo FROM 'a/redo.csv' (FORMAT 'csv');\n";
+      let history   = "COPY objects FROM 'a/objects.csv' (FORMAT 'csv');\nCOPY history FROM 'a/history.csv' (FORMAT 'csv');\nCOPY changes FROM 'a/changes.csv' (FORMAT 'csv');\nCOPY head FROM 'a/head.csv' (FORMAT 'csv');\n";
+      let format_1  = SaveVersion { format: 1, level_checksum: None };
+      assert_eq!(format_1.migrate(undo_redo.to_string(), Some(1)), Ok("COPY objects FROM 'a/objects.csv' (FORMAT 'csv');\n".to_string()));
+      assert_eq!(format_1.migrate(history.to_string(), Some(1)), Ok(history.to_string()));
+      assert_eq!(SaveVersion::current(Some(1)).migrate(history.to_string(), Some(1)), Ok(history.to_string()));
+      assert!(SaveVersion::current(Some(1)).migrate(history.to_string(), Some(2)).is_err());
+      assert!(SaveVersion { format: SAVE_FORMAT_VERSION+1, level_checksum: None }.migrate(history.to_string(), None).is_err());
+    }
+
+    #[test]
+    fn unique_temp_directories() -> io::Result<()> {
+      let (first, second) = (TempDirectory::new("test")?, TempDirectory::new("test")?);
+      assert_ne!(first.path(), second.path());
+      assert!(first.path().is_dir());
+      let path = first.path().to_path_buf();
+      drop(first);
+      assert!(!path.exists());
+      Ok(())
+    }
+}
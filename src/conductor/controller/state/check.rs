@@ -0,0 +1,374 @@
+use std::fmt;
+#[cfg(feature = "duckdb")]
+use duckdb::params;
+
+use crate::engine::Board;
+
+use super::{error, header, Completeness, State};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity { Warning, Error }
+
+impl fmt::Display for Severity {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Warning => write!(f, "warning"),
+      Self::Error   => write!(f, "error")
+    }
+  }
+}
+
+/// A problem found in a level, located at (column, line) of the level file, if it concerns a single position
+#[derive(Debug, PartialEq, Eq)]
+pub struct Diagnostic { pub severity: Severity, pub pos: Option<(u16,u16)>, pub message: String }
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.pos {
+      Some((x,y)) => write!(f, "{}:{}: {}: {}", y, x, self.severity, self.message),
+      None        => write!(f, "{}: {}", self.severity, self.message)
+    }
+  }
+}
+
+impl State {
+  #[cfg(feature = "duckdb")]
+  fn diagnostics_via_query(&self, severity: Severity, query: &str) -> Result<Vec<Diagnostic>, error::IOError> {
+    Ok(self.db.prepare(query)?
+      .query_map(params![], |row| Ok(Diagnostic { severity, pos: Some((row.get(0)?, row.get(1)?)), message: row.get(2)? }))?
+      .collect::<Result<Vec<Diagnostic>, duckdb::Error>>()?)
+  }
+
+  /// Lint the level without loading it into the board
+  #[cfg(feature = "duckdb")]
+  fn lint_level(&self, level_string: &str) -> Result<Vec<Diagnostic>, error::IOError> {
+    // Create temporary tables of all rows and characters, including unknown ones
+    self.db.execute_batch(r"
+      create temporary table checked_rows (
+        y     int not null,
+        width int not null
+      );
+
+      create temporary table checked_cells (
+        c          varchar not null,
+        connectors int,
+        kind       kind    not null,
+        x          int     not null,
+        y          int     not null
+      );
+
+      -- Every direction to move to a neighbouring cell
+      create temporary table checked_steps as
+        select * from (values (0,-1),(1,0),(0,1),(-1,0)) as s(dx,dy);
+    ")?;
+
+    self.db.execute(r"
+      insert into checked_rows(y,width)
+        select  y, length(rows[y])
+        from    (select string_split_regex(?1,'(\r\n|[\r\n])') as rows),
+        lateral (select generate_subscripts(rows,1)            as y);
+    ", params![level_string])?;
+
+    // Map every character onto its connectors and kind like the engine does, an unknown character has no connectors
+    {
+      let mut insert = self.db.prepare("insert into checked_cells(c,connectors,kind,x,y) values (?1,?2,?3,?4,?5)")?;
+      for (y, row) in level_string.replace("\r\n", "\n").split(['\r','\n']).enumerate() {
+        for (x, c) in row.chars().enumerate().filter(|(_,c)| *c != ' ') {
+          insert.execute(params![c.to_string(), Board::char_to_connectors(c), Board::char_to_kind(c).to_string(), x+1, y+1])?;
+        }
+      }
+    }
+
+    // All cells of the level, surrounded by one cell of space
+    self.db.execute_batch(r"
+      create temporary table checked_grid as
+        select  x, y
+        from    (select coalesce(max(r.width),0)+2 as w, count(*)+2 as h from checked_rows as r),
+        lateral (select unnest(range(0,w)) as x),
+        lateral (select unnest(range(0,h)) as y);
+    ")?;
+
+    let mut diagnostics = Vec::new();
+
+    diagnostics.extend(self.diagnostics_via_query(Severity::Error, r"
+      select cc.x, cc.y, format('Unknown character `{}`', cc.c)
+      from   checked_cells as cc
+      where  cc.connectors is null
+      order by cc.y, cc.x
+    ")?);
+
+    diagnostics.extend(self.diagnostics_via_query(Severity::Error, r"
+      select cc.x, cc.y, format('Teleporter `{}` must appear exactly twice, but appears {} times', cc.c, count(*) over (partition by cc.c))
+      from   checked_cells as cc
+      where  cc.kind = 'Teleporter'
+      qualify count(*) over (partition by cc.c) <> 2
+      order by cc.y, cc.x
+    ")?);
+
+    diagnostics.extend(self.diagnostics_via_query(Severity::Warning, r"
+      select 1, cr.y, format('Row is {} characters wide, but the widest row has {}', cr.width, (select max(_cr.width) from checked_rows as _cr))
+      from   checked_rows as cr
+      where  cr.width <> (select max(_cr.width) from checked_rows as _cr)
+      -- Ignore the empty line after a trailing line break
+      and    not (cr.width = 0 and cr.y = (select max(_cr.y) from checked_rows as _cr))
+      order by cr.y
+    ")?);
+
+    // Parts that can be moved out of the level, since they are reachable from the space surrounding it
+    diagnostics.extend(self.diagnostics_via_query(Severity::Error, r"
+      with recursive outside(x,y) as (
+        select 0, 0
+          union
+        select g.x, g.y
+        from   outside as o, checked_steps as s, checked_grid as g
+        where  (g.x,g.y) = (o.x+s.dx,o.y+s.dy)
+        -- A lock only opens next to a key, which is enclosed like any other part, so it encloses like a wall
+        and    not exists (select 1
+                           from   checked_cells as cc
+                           where  (cc.x,cc.y) = (g.x,g.y)
+                           and    cc.connectors = 0
+                           and    cc.kind in ('None','Lock'))
+      )
+      select cc.x, cc.y, format('Part `{}` is not enclosed by an outer wall', cc.c)
+      from   checked_cells as cc, outside as o
+      where  (cc.x,cc.y) = (o.x,o.y)
+      order by cc.y, cc.x
+    ")?);
+
+    // Locks without any key in the level never open and are walls like any other
+    diagnostics.extend(self.diagnostics_via_query(Severity::Warning, r"
+      select cc.x, cc.y, format('Connector of `{}` pointing {} points into a wall', cc.c,
+                                case when b.dy < 0 then 'up' when b.dx > 0 then 'right' when b.dy > 0 then 'down' else 'left' end)
+      from   checked_cells as cc, connector_bits() as b
+      where  (cc.connectors & b.bit) = b.bit
+      and    cc.kind <> 'Rotor' -- A Rotor turns its connectors away from the wall
+      and    exists (select 1
+                     from   checked_cells as w
+                     where  (w.x,w.y) = (cc.x+b.dx,cc.y+b.dy)
+                     and    w.connectors = 0
+                     and    (w.kind = 'None' or (w.kind = 'Lock' and not exists (select 1 from checked_cells as k where k.kind = 'Key'))))
+      order by cc.y, cc.x, b.bit desc
+    ")?);
+
+    diagnostics.extend(self.diagnostics_via_query(Severity::Error, r#"
+      select cc.x, cc.y, format('{} connector of `{}` pointing {} has no {} partner anywhere', cc.kind, cc.c,
+                                case when b.dy < 0 then 'up' when b.dx > 0 then 'right' when b.dy > 0 then 'down' else 'left' end,
+                                cc.kind)
+      from   checked_cells as cc, connector_bits() as b
+      where  b.special
+      and    (cc.connectors & b.bit) = b.bit
+      and    not exists (select 1
+                         from   checked_cells as _cc
+                         where  (_cc.connectors & b.counterpart) = b.counterpart
+                         and    _cc.kind = cc.kind)
+      order by cc.y, cc.x, b.bit desc
+    "#)?);
+
+    // Empty cells inside the level, which no part can ever reach, grouped into regions by their first cell,
+    // parts reach through locks only, if a key can open them
+    diagnostics.extend(self.diagnostics_via_query(Severity::Warning, r"
+      with recursive outside(x,y) as (
+        select 0, 0
+          union
+        select g.x, g.y
+        from   outside as o, checked_steps as s, checked_grid as g
+        where  (g.x,g.y) = (o.x+s.dx,o.y+s.dy)
+        and    not exists (select 1 from checked_cells as cc where (cc.x,cc.y) = (g.x,g.y))
+      ),
+      reachable(x,y) as (
+        select cc.x, cc.y
+        from   checked_cells as cc
+        where  cc.connectors > 0 or cc.kind = 'Volatile'
+          union
+        select g.x, g.y
+        from   reachable as r, checked_steps as s, checked_grid as g
+        where  (g.x,g.y) = (r.x+s.dx,r.y+s.dy)
+        and    not exists (select 1
+                           from   checked_cells as cc
+                           where  (cc.x,cc.y) = (g.x,g.y)
+                           and    cc.connectors = 0
+                           and    (cc.kind = 'None' or (cc.kind = 'Lock' and not exists (select 1 from checked_cells as k where k.kind = 'Key'))))
+      ),
+      unreachable as (
+        select g.x, g.y
+        from   checked_grid as g
+        where  not exists (select 1 from checked_cells as cc where (cc.x,cc.y) = (g.x,g.y))
+        and    not exists (select 1 from outside       as o  where (o.x,o.y)   = (g.x,g.y))
+        and    not exists (select 1 from reachable     as r  where (r.x,r.y)   = (g.x,g.y))
+      ),
+      region(sx,sy,x,y) as (
+        select u.x, u.y, u.x, u.y
+        from   unreachable as u
+          union
+        select r.sx, r.sy, u.x, u.y
+        from   region as r, checked_steps as s, unreachable as u
+        where  (u.x,u.y) = (r.x+s.dx,r.y+s.dy)
+      )
+      select first % 65536, first // 65536, format('Enclosed region of {} empty cells cannot be reached by any part', count(*))
+      from   (select min(r.sy*65536+r.sx) as first
+              from   region as r
+              group by r.x, r.y)
+      group by first
+      order by first
+    ")?);
+
+    self.db.execute_batch(r"
+      drop table checked_grid;
+      drop table checked_steps;
+      drop table checked_cells;
+      drop table checked_rows;
+    ")?;
+
+    Ok(diagnostics)
+  }
+
+  /// Lint the level for unknown characters only, the other lints need DuckDB
+  #[cfg(not(feature = "duckdb"))]
+  fn lint_level(&self, level_string: &str) -> Result<Vec<Diagnostic>, error::IOError> {
+    Ok(level_string.replace("\r\n", "\n").split(['\r','\n']).enumerate()
+      .flat_map(|(y, row)| row.chars().enumerate().map(move |(x, c)| (c, (x as u16 + 1, y as u16 + 1))))
+      .filter(|&(c,_)| c != ' ' && Board::char_to_connectors(c).is_none())
+      .map(|(c, pos)| Diagnostic { severity: Severity::Error, pos: Some(pos), message: format!("Unknown character `{}`", c) })
+      .collect())
+  }
+
+  /// Lint the level and, if it has no errors, load it to check whether it can ever be completed
+  pub fn check(mut self, level_string: &str) -> Result<Vec<Diagnostic>, error::IOError> {
+    #[cfg(feature = "duckdb")]
+    self.init_database()?;
+    let (_, grid_string, grid_line) = match header::LevelHeader::split(level_string) {
+      Ok(split)                                          => split,
+      Err(error::IOError::ParseLevelHeaderError(line,e)) => return Ok(vec![Diagnostic { severity: Severity::Error, pos: Some((1,line)), message: e }]),
+      Err(e)                                             => return Err(e)
+    };
+    // Lines of the grid follow the header
+    let mut diagnostics: Vec<Diagnostic> = self.lint_level(grid_string)?.into_iter()
+      .map(|d| Diagnostic { pos: d.pos.map(|(x,y)| (x,y+grid_line)), ..d })
+      .collect();
+    if diagnostics.iter().all(|d| d.severity != Severity::Error) {
+      match self.load_level(level_string.to_string()) {
+        Err(e) => diagnostics.push(Diagnostic { severity: Severity::Error, pos: None, message: format!("Level cannot be loaded: {}", e) }),
+        Ok(_)  => if self.board.completeness() == Completeness::Unsolvable {
+          diagnostics.push(Diagnostic { severity: Severity::Warning, pos: None, message: "Level can never be completed".to_string() });
+        }
+      }
+    }
+    // Order by position, diagnostics of the whole level last
+    diagnostics.sort_by_key(|d| (d.pos.is_none(), d.pos.map(|(x,y)| (y,x))));
+    #[cfg(feature = "duckdb")]
+    self.shutdown_database()?;
+    Ok(diagnostics)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(level: &str) -> Result<Vec<Diagnostic>, error::IOError> {
+      let (state, _, _) = State::new()?;
+      state.check(level)
+    }
+
+    #[test]
+    fn check_valid_level() -> error::IOResult {
+      assert_eq!(check("█████\n█╶ ╴█\n█████")?, vec![]);
+      Ok(())
+    }
+
+    #[test]
+    fn check_unknown_character() -> error::IOResult {
+      assert_eq!(check("█████\n█╶x╴█\n█████")?, vec![
+        Diagnostic { severity: Severity::Error, pos: Some((3,2)), message: "Unknown character `x`".to_string() }
+      ]);
+      Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "duckdb")]
+    fn check_missing_outer_wall() -> error::IOResult {
+      assert_eq!(check("█████\n█╶ ╴\n█████")?, vec![
+        Diagnostic { severity: Severity::Warning, pos: Some((1,2)), message: "Row is 4 characters wide, but the widest row has 5".to_string() },
+        Diagnostic { severity: Severity::Error,   pos: Some((2,2)), message: "Part `╶` is not enclosed by an outer wall".to_string() },
+        Diagnostic { severity: Severity::Error,   pos: Some((4,2)), message: "Part `╴` is not enclosed by an outer wall".to_string() }
+      ]);
+      Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "duckdb")]
+    fn check_wall_and_door_connectors() -> error::IOResult {
+      assert_eq!(check("█████\n█╶█╔█\n█████")?, vec![
+        Diagnostic { severity: Severity::Warning, pos: Some((2,2)), message: "Connector of `╶` pointing right points into a wall".to_string() },
+        Diagnostic { severity: Severity::Warning, pos: Some((4,2)), message: "Connector of `╔` pointing right points into a wall".to_string() },
+        Diagnostic { severity: Severity::Warning, pos: Some((4,2)), message: "Connector of `╔` pointing down points into a wall".to_string() },
+        Diagnostic { severity: Severity::Error,   pos: Some((4,2)), message: "Door connector of `╔` pointing right has no Door partner anywhere".to_string() },
+        Diagnostic { severity: Severity::Error,   pos: Some((4,2)), message: "Door connector of `╔` pointing down has no Door partner anywhere".to_string() }
+      ]);
+      Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "duckdb")]
+    fn check_unpaired_teleporter() -> error::IOResult {
+      assert_eq!(check("███████\n█╶①②╴ █\n█ ②   █\n███████")?, vec![
+        Diagnostic { severity: Severity::Error, pos: Some((3,2)), message: "Teleporter `①` must appear exactly twice, but appears 1 times".to_string() }
+      ]);
+      Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "duckdb")]
+    fn check_unreachable_region() -> error::IOResult {
+      assert_eq!(check("███████\n█╶ ╴█ █\n███████")?, vec![
+        Diagnostic { severity: Severity::Warning, pos: Some((6,2)), message: "Enclosed region of 1 empty cells cannot be reached by any part".to_string() }
+      ]);
+      Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "duckdb")]
+    fn check_locks_as_walls() -> error::IOResult {
+      // A lock in the outer wall encloses the level
+      assert_eq!(check("█████\n▓╶ ╴█\n█████")?, vec![]);
+      // Without a key, a lock is a wall to point into and to reach through
+      assert_eq!(check("████████\n█╶ ╴▓╴ █\n████████")?, vec![
+        Diagnostic { severity: Severity::Warning, pos: Some((6,2)), message: "Connector of `╴` pointing left points into a wall".to_string() }
+      ]);
+      assert_eq!(check("███████\n█╶ ╴▓ █\n███████")?, vec![
+        Diagnostic { severity: Severity::Warning, pos: Some((6,2)), message: "Enclosed region of 1 empty cells cannot be reached by any part".to_string() }
+      ]);
+      // A key opens locks, so they are no walls
+      assert_eq!(check("███████\n█╶ ⊣▓ █\n███████")?, vec![]);
+      Ok(())
+    }
+
+    #[test]
+    fn check_level_with_header() -> error::IOResult {
+      assert_eq!(check("title: Unknown\n---\n█████\n█╶x╴█\n█████")?, vec![
+        Diagnostic { severity: Severity::Error, pos: Some((3,4)), message: "Unknown character `x`".to_string() }
+      ]);
+      assert_eq!(check("title: Unknown\nsize: 3\n---\n█████\n█╶ ╴█\n█████")?, vec![
+        Diagnostic { severity: Severity::Error, pos: Some((1,2)), message: "Unknown key `size`".to_string() }
+      ]);
+      Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "duckdb")]
+    fn check_unsolvable_level() -> error::IOResult {
+      assert_eq!(check("████\n█╶╶█\n████")?, vec![
+        Diagnostic { severity: Severity::Warning, pos: Some((3,2)), message: "Connector of `╶` pointing right points into a wall".to_string() },
+        Diagnostic { severity: Severity::Warning, pos: None, message: "Level can never be completed".to_string() }
+      ]);
+      Ok(())
+    }
+
+    #[test]
+    fn check_level_never_completed() -> error::IOResult {
+      assert_eq!(check("██████\n█╶╶  █\n██████")?, vec![
+        Diagnostic { severity: Severity::Warning, pos: None, message: "Level can never be completed".to_string() }
+      ]);
+      Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use std::str::FromStr;
+
+use super::{error, Completeness};
+
+const HEADER_SEPARATOR: &str = "---";
+
+/// Optional metadata in front of the grid of a level, given as `key: value` lines and ended by a `---` line
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LevelHeader {
+  pub title       : Option<String>,
+  pub author      : Option<String>,
+  pub difficulty  : Option<String>,
+  pub par         : Option<i32>,
+  pub hint        : Option<String>,
+  pub completeness: Option<Completeness>
+}
+
+impl LevelHeader {
+  /// Split a level into its header, its grid and the number of lines in front of the grid
+  pub fn split(level_string: &str) -> Result<(LevelHeader, &str, u16), error::IOError> {
+    let Some(separator) = level_string.lines().position(|line| line.trim_end() == HEADER_SEPARATOR) else {
+      return Ok((LevelHeader::default(), level_string, 0));
+    };
+    let mut header = LevelHeader::default();
+    for (index, line) in level_string.lines().take(separator).enumerate() {
+      if line.trim().is_empty() { continue }
+      let line_number = index as u16 + 1;
+      let Some((key, value)) = line.split_once(':') else {
+        return Err(error::IOError::ParseLevelHeaderError(line_number, format!("Expected `key: value`, but got `{}`", line)));
+      };
+      let value = value.trim().to_string();
+      match key.trim() {
+        "title"        => header.title        = Some(value),
+        "author"       => header.author       = Some(value),
+        "difficulty"   => header.difficulty   = Some(value),
+        "hint"         => header.hint         = Some(value),
+        "par"          => header.par          = Some(value.parse().map_err(|_| error::IOError::ParseLevelHeaderError(line_number, format!("Par `{}` is not a number of turns", value)))?),
+        "completeness" => header.completeness = Some(match Completeness::from_str(value.as_str()) {
+          Ok(completeness@(Completeness::Complete | Completeness::PartiallyComplete)) => completeness,
+          _ => return Err(error::IOError::ParseLevelHeaderError(line_number, format!("Completeness `{}` is neither `Complete` nor `PartiallyComplete`", value)))
+        }),
+        key            => return Err(error::IOError::ParseLevelHeaderError(line_number, format!("Unknown key `{}`", key)))
+      }
+    }
+    let grid_start = level_string.split_inclusive('\n').take(separator+1).map(str::len).sum::<usize>();
+    Ok((header, &level_string[grid_start..], separator as u16 + 1))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_without_header() -> error::IOResult {
+      assert_eq!(LevelHeader::split("████\n█╶╴█\n████")?, (LevelHeader::default(), "████\n█╶╴█\n████", 0));
+      Ok(())
+    }
+
+    #[test]
+    fn split_with_header() -> error::IOResult {
+      let (header, grid, grid_line) = LevelHeader::split("title: First\nauthor: Someone\r\npar: 3\n\ncompleteness: Complete\n---\n████\n█╶╴█\n████")?;
+      assert_eq!(header, LevelHeader {
+        title       : Some("First".to_string()),
+        author      : Some("Someone".to_string()),
+        par         : Some(3),
+        completeness: Some(Completeness::Complete),
+        ..LevelHeader::default()
+      });
+      assert_eq!(grid, "████\n█╶╴█\n████");
+      assert_eq!(grid_line, 6);
+      Ok(())
+    }
+
+    #[test]
+    fn split_with_invalid_header() {
+      assert!(matches!(LevelHeader::split("par: many\n---\n████"), Err(error::IOError::ParseLevelHeaderError(1, _))));
+      assert!(matches!(LevelHeader::split("title: First\nsize: 3\n---\n████"), Err(error::IOError::ParseLevelHeaderError(2, _))));
+    }
+}
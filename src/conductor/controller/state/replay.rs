@@ -0,0 +1,128 @@
+use std::{fs, str::FromStr, sync::mpsc::{Receiver, RecvTimeoutError, SyncSender}, time};
+
+use super::{error, ControlStatePayload, Direction};
+
+pub const REPLAY_FILE_EXTENSION: &str = "rpl";
+const LEVEL_KEY                : &str = "level:";
+
+/// Payloads changing the board of a level, each with the milliseconds passed since the level started
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replay {
+  pub level_path: String,
+  pub steps     : Vec<(u64,ControlStatePayload)>
+}
+
+impl Replay {
+  pub fn new(level_path: String) -> Self { Self { level_path, steps: Vec::new() } }
+
+  /// Only payloads changing the board, or what a later select acts on, are replayed
+  /// Saved games are neither written, deleted nor loaded by a replay, as they depend on the saves at hand
+  #[inline]
+  pub fn is_recorded(payload: &ControlStatePayload) -> bool {
+    matches!(payload,
+      ControlStatePayload::MoveCursor(_) | ControlStatePayload::SetCursorPosition(_) | ControlStatePayload::Select |
+      ControlStatePayload::Undo | ControlStatePayload::Redo | ControlStatePayload::PreviousBranch | ControlStatePayload::NextBranch |
+      ControlStatePayload::GotoTurn(_) | ControlStatePayload::Rotate)
+  }
+
+  #[inline]
+  pub fn record(&mut self, millis: u64, payload: &ControlStatePayload) {
+    if Replay::is_recorded(payload) {
+      self.steps.push((millis, payload.clone()));
+    }
+  }
+
+  /// A replay starts with the `level:` line, followed by one `millis payload` line per step
+  pub fn parse(replay_string: &str) -> Result<Self, error::IOError> {
+    let mut lines = replay_string.lines().enumerate().filter(|(_,line)| !line.trim().is_empty());
+    let level_path = match lines.next() {
+      Some((_, line)) if line.starts_with(LEVEL_KEY) => line[LEVEL_KEY.len()..].trim().to_string(),
+      _                                              => return Err(error::IOError::ParseReplayError(1, format!("Expected `{} <path>` in the first line", LEVEL_KEY)))
+    };
+    let steps = lines.map(|(index, line)| {
+      let line_number = index+1;
+      let mut words   = line.split_whitespace();
+      let millis      = words.next().and_then(|millis| millis.parse().ok())
+        .ok_or_else(|| error::IOError::ParseReplayError(line_number, format!("Expected milliseconds in front of `{}`", line)))?;
+      let payload = match (words.next(), words.next(), words.next(), words.next()) {
+        (Some("MoveCursor"       ), Some(direction), None, None) => Direction::from_str(direction).ok().map(ControlStatePayload::MoveCursor),
+        (Some("SetCursorPosition"), Some(x), Some(y), None)      => x.parse().ok().zip(y.parse().ok()).map(ControlStatePayload::SetCursorPosition),
+        (Some("Select"           ), None, None, None)            => Some(ControlStatePayload::Select),
+        (Some("Undo"             ), None, None, None)            => Some(ControlStatePayload::Undo),
+        (Some("Redo"             ), None, None, None)            => Some(ControlStatePayload::Redo),
+        (Some("PreviousBranch"   ), None, None, None)            => Some(ControlStatePayload::PreviousBranch),
+        (Some("NextBranch"       ), None, None, None)            => Some(ControlStatePayload::NextBranch),
+        (Some("GotoTurn"         ), Some(turn), None, None)      => turn.parse().ok().map(ControlStatePayload::GotoTurn),
+        (Some("Rotate"           ), None, None, None)            => Some(ControlStatePayload::Rotate),
+        _                                                        => None
+      }.ok_or_else(|| error::IOError::ParseReplayError(line_number, format!("Unknown step `{}`", line)))?;
+      Ok((millis, payload))
+    }).collect::<Result<Vec<_>,error::IOError>>()?;
+    Ok(Self { level_path, steps })
+  }
+
+  pub fn to_replay_string(&self) -> String {
+    let steps: String = self.steps.iter().map(|(millis, payload)| match payload {
+      ControlStatePayload::MoveCursor(direction)    => format!("{} MoveCursor {:?}\n", millis, direction),
+      ControlStatePayload::SetCursorPosition((x,y)) => format!("{} SetCursorPosition {} {}\n", millis, x, y),
+      ControlStatePayload::Select                   => format!("{} Select\n", millis),
+      ControlStatePayload::Undo                     => format!("{} Undo\n", millis),
+      ControlStatePayload::Redo                     => format!("{} Redo\n", millis),
+      ControlStatePayload::PreviousBranch           => format!("{} PreviousBranch\n", millis),
+      ControlStatePayload::NextBranch               => format!("{} NextBranch\n", millis),
+      ControlStatePayload::GotoTurn(turn)           => format!("{} GotoTurn {}\n", millis, turn),
+      ControlStatePayload::Rotate                   => format!("{} Rotate\n", millis),
+      _                                             => String::new()
+    }).collect();
+    format!("{} {}\n{}", LEVEL_KEY, self.level_path, steps)
+  }
+
+  pub fn load(replay_path: &str) -> Result<Self, error::IOError> { Replay::parse(fs::read_to_string(replay_path)?.as_str()) }
+
+  pub fn save(&self, replay_path: &str) -> error::IOResult { fs::write(replay_path, self.to_replay_string())?; Ok(()) }
+
+  /// Send every step to the state in the recorded rhythm, `speed` times as fast, until all steps are sent or `stop_recv` disconnects
+  pub fn play(self, speed: f64, control_state_send: SyncSender<ControlStatePayload>, stop_recv: Receiver<()>) -> error::IOResult {
+    let mut last_millis = 0;
+    for (millis, payload) in self.steps {
+      let delay = time::Duration::from_millis(millis.saturating_sub(last_millis)).div_f64(speed);
+      last_millis = millis;
+      match stop_recv.recv_timeout(delay) {
+        Err(RecvTimeoutError::Timeout) => control_state_send.send(payload)?,
+        _                              => break
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_round_trip() -> error::IOResult {
+      let mut replay = Replay::new("levels/01-first.lvl".to_string());
+      replay.record(0,   &ControlStatePayload::SetCursorPosition((3,2)));
+      replay.record(120, &ControlStatePayload::Select);
+      replay.record(180, &ControlStatePayload::SetBoardSize((80,24)));
+      replay.record(250, &ControlStatePayload::MoveCursor(Direction::UpLeft));
+      replay.record(400, &ControlStatePayload::Undo);
+      replay.record(410, &ControlStatePayload::Hint);
+      replay.record(450, &ControlStatePayload::Load);
+      replay.record(460, &ControlStatePayload::Save("Turn 1".to_string()));
+      replay.record(470, &ControlStatePayload::DeleteSlot);
+      replay.record(520, &ControlStatePayload::Rotate);
+      assert_eq!(replay.steps.len(), 5);
+      assert_eq!(replay.to_replay_string(), "level: levels/01-first.lvl\n0 SetCursorPosition 3 2\n120 Select\n250 MoveCursor UpLeft\n400 Undo\n520 Rotate\n");
+      assert_eq!(Replay::parse(replay.to_replay_string().as_str())?, replay);
+      Ok(())
+    }
+
+    #[test]
+    fn parse_invalid_replay() {
+      assert!(matches!(Replay::parse("0 Select"), Err(error::IOError::ParseReplayError(1, _))));
+      assert!(matches!(Replay::parse("level: a.lvl\n0 Select\nsoon Undo"), Err(error::IOError::ParseReplayError(3, _))));
+      assert!(matches!(Replay::parse("level: a.lvl\n0 MoveCursor Sideways"), Err(error::IOError::ParseReplayError(2, _))));
+    }
+}
@@ -0,0 +1,107 @@
+use std::{fs, io, path::Path, str::FromStr};
+use chrono::{offset::Local, DateTime};
+use std::time::SystemTime;
+
+use super::Completeness;
+
+pub const SAVE_FILE_EXTENSION: &str = "sav";
+const META_FILE_EXTENSION    : &str = "meta";
+const LEVEL_FILE_EXTENSION   : &str = "lvl";
+const SLOT_FILE_FORMAT       : &str = "%Y-%m-%d_%H-%M-%S-%3f";
+const SAVED_FORMAT           : &str = "%Y-%m-%d %H:%M:%S";
+
+/// A saved game with its name, the time it was saved at and the turn count and completeness of its board
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Slot {
+  pub save_path   : String,
+  pub name        : String,
+  pub saved       : String,
+  pub turn        : i32,
+  pub completeness: Completeness
+}
+
+/// Path of a file in `slot_directory` named by the time `now`, so it never replaces another file
+#[inline]
+fn timestamped_path(slot_directory: &str, now: DateTime<Local>, extension: &str) -> String {
+  Path::new(slot_directory).join(format!("{}.{}", now.format(SLOT_FILE_FORMAT), extension)).to_string_lossy().to_string()
+}
+
+/// Path of a board exported as level into `slot_directory` now
+pub fn export_path(slot_directory: &str) -> String { timestamped_path(slot_directory, SystemTime::now().into(), LEVEL_FILE_EXTENSION) }
+
+impl Slot {
+  /// Create a new slot in `slot_directory`, named by the current time, so it never replaces another slot
+  pub fn new(slot_directory: &str, name: String, turn: i32, completeness: Completeness) -> Self {
+    let now = Into::<DateTime<Local>>::into(SystemTime::now());
+    Self { save_path: timestamped_path(slot_directory, now, SAVE_FILE_EXTENSION), name, saved: now.format(SAVED_FORMAT).to_string(), turn, completeness }
+  }
+
+  #[inline]
+  fn meta_path(save_path: &str) -> String { Path::new(save_path).with_extension(META_FILE_EXTENSION).to_string_lossy().to_string() }
+
+  /// Every line of the metadata holds a `key: value` pair, unknown keys are skipped
+  fn parse(save_path: String, meta_string: &str) -> Option<Self> {
+    let (mut name, mut saved, mut turn, mut completeness) = (None, None, None, None);
+    for (key, value) in meta_string.lines().filter_map(|line| line.split_once(':')) {
+      let value = value.trim();
+      match key.trim() {
+        "name"         => name         = Some(value.to_string()),
+        "saved"        => saved        = Some(value.to_string()),
+        "turn"         => turn         = value.parse().ok(),
+        "completeness" => completeness = Completeness::from_str(value).ok(),
+        _              => ()
+      }
+    }
+    Some(Self { save_path, name: name?, saved: saved?, turn: turn?, completeness: completeness? })
+  }
+
+  fn to_meta_string(&self) -> String {
+    format!("name: {}\nsaved: {}\nturn: {}\ncompleteness: {}\n", self.name, self.saved, self.turn, self.completeness)
+  }
+
+  /// All slots with a save file and readable metadata, the latest first
+  pub fn list(slot_directory: &str) -> io::Result<Vec<Self>> {
+    if !Path::new(slot_directory).is_dir() { return Ok(Vec::new()) }
+    let mut slots: Vec<Self> = fs::read_dir(slot_directory)?
+      .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+      .filter(|path| path.is_file() && path.extension().is_some_and(|extension| extension == SAVE_FILE_EXTENSION))
+      .filter_map(|path| {
+        let save_path = path.to_string_lossy().to_string();
+        fs::read_to_string(Slot::meta_path(save_path.as_str())).ok().and_then(|meta_string| Slot::parse(save_path, meta_string.as_str()))
+      })
+      .collect();
+    slots.sort_by(|a, b| b.save_path.cmp(&a.save_path));
+    Ok(slots)
+  }
+
+  /// Write the metadata next to the save file
+  pub fn write_meta(&self) -> io::Result<()> { fs::write(Slot::meta_path(self.save_path.as_str()), self.to_meta_string()) }
+
+  pub fn delete(&self) -> io::Result<()> {
+    for path in [self.save_path.clone(), Slot::meta_path(self.save_path.as_str())] {
+      if Path::new(path.as_str()).exists() {
+        fs::remove_file(path)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_round_trip() {
+      let slot = Slot {
+        save_path   : "levels/01-first.saves/2024-01-02_03-04-05-678.sav".to_string(),
+        name        : "Before the door: take two".to_string(),
+        saved       : "2024-01-02 03:04:05".to_string(),
+        turn        : 12,
+        completeness: Completeness::PartiallyComplete
+      };
+      assert_eq!(Slot::meta_path(slot.save_path.as_str()), "levels/01-first.saves/2024-01-02_03-04-05-678.meta");
+      assert_eq!(Slot::parse(slot.save_path.clone(), slot.to_meta_string().as_str()), Some(slot.clone()));
+      assert_eq!(Slot::parse(slot.save_path.clone(), "name: Incomplete metadata"), None);
+    }
+}
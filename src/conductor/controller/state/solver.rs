@@ -0,0 +1,42 @@
+pub use crate::engine::solver::{Action, Move, Search, SearchEnd, Searcher, DEFAULT_SEARCH_LIMIT};
+use crate::engine::solver;
+
+use super::State;
+
+impl State {
+  /// Search for a short, not necessarily the shortest, sequence of shape moves from the current board by the engine, see `engine::solver::search`
+  pub fn search(&self, limit: usize) -> Search { solver::search(&self.board, limit) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{error, Completeness};
+
+    #[test]
+    /// █████    █████
+    /// █╶ ╴█ -> █ ╶╴█
+    /// █████    █████
+    fn solve_single_move() -> error::IOResult {
+      let (mut state, _, _) = State::new()?;
+      state.load_level("█████\n█╶ ╴█\n█████".to_string())?;
+      let search = state.search(DEFAULT_SEARCH_LIMIT);
+      let solution = search.solution.expect("No solution found, where one was expected");
+      assert_eq!(solution.moves.len(), 1);
+      assert_eq!(solution.completeness, Completeness::Complete);
+      Ok(())
+    }
+
+    #[test]
+    /// ████
+    /// █╶╶█
+    /// ████
+    fn solve_impossible() -> error::IOResult {
+      let (mut state, _, _) = State::new()?;
+      state.load_level("████\n█╶╶█\n████".to_string())?;
+      let search = state.search(DEFAULT_SEARCH_LIMIT);
+      assert_eq!(search.solution, None);
+      assert_eq!(search.end, SearchEnd::Unsolvable);
+      Ok(())
+    }
+}
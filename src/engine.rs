@@ -0,0 +1,1109 @@
+pub mod solver;
+
+use std::{collections::{btree_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet, VecDeque}, cmp::max, fmt, str::FromStr};
+
+/// Each connector bit with the direction (Δx,Δy) it points to, the bit it connects to and whether it is a special connector
+const CONNECTOR_BITS: [(i32,(i32,i32),i32,bool); 8] = [
+  (128,( 0,-1), 32,true ),
+  ( 64,( 1, 0), 16,true ),
+  ( 32,( 0, 1),128,true ),
+  ( 16,(-1, 0), 64,true ),
+  (  8,( 0,-1),  2,false),
+  (  4,( 1, 0),  1,false),
+  (  2,( 0, 1),  8,false),
+  (  1,(-1, 0),  4,false)
+];
+const NORMAL_CONNECTORS : i32 = 15;
+const SPECIAL_CONNECTORS: i32 = 240;
+/// Gates pointing the same way share the shape `GATE_SHAPE` minus the normal connector bit of their direction
+pub const GATE_SHAPE    : i32 = -10;
+/// All ice shares one shape
+const ICE_SHAPE         : i32 = -20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Completeness { Complete, PartiallyComplete, Incomplete, Unsolvable }
+
+impl fmt::Display for Completeness {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Complete          => write!(f, "Complete"),
+      Self::PartiallyComplete => write!(f, "PartiallyComplete"),
+      Self::Incomplete        => write!(f, "Incomplete"),
+      Self::Unsolvable        => write!(f, "Unsolvable")
+    }
+  }
+}
+
+impl FromStr for Completeness {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "Complete"          => Ok(Self::Complete),
+      "PartiallyComplete" => Ok(Self::PartiallyComplete),
+      "Incomplete"        => Ok(Self::Incomplete),
+      "Unsolvable"        => Ok(Self::Unsolvable),
+      _                   => Err("Failed to parse string to type `Completeness`".to_string())
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction { Up, UpRight, Right, DownRight, Down, DownLeft, Left, UpLeft }
+
+impl FromStr for Direction {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "Up"        => Ok(Self::Up),
+      "UpRight"   => Ok(Self::UpRight),
+      "Right"     => Ok(Self::Right),
+      "DownRight" => Ok(Self::DownRight),
+      "Down"      => Ok(Self::Down),
+      "DownLeft"  => Ok(Self::DownLeft),
+      "Left"      => Ok(Self::Left),
+      "UpLeft"    => Ok(Self::UpLeft),
+      _           => Err("Failed to parse string to type `Direction`".to_string())
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Kind { None, Wide, Door, Volatile, Rotor, Teleporter, Gate, Ice, Key, Lock }
+
+impl fmt::Display for Kind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::None       => write!(f, "None"),
+      Self::Wide       => write!(f, "Wide"),
+      Self::Door       => write!(f, "Door"),
+      Self::Volatile   => write!(f, "Volatile"),
+      Self::Rotor      => write!(f, "Rotor"),
+      Self::Teleporter => write!(f, "Teleporter"),
+      Self::Gate       => write!(f, "Gate"),
+      Self::Ice        => write!(f, "Ice"),
+      Self::Key        => write!(f, "Key"),
+      Self::Lock       => write!(f, "Lock")
+    }
+  }
+}
+
+impl FromStr for Kind {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "None"       => Ok(Self::None),
+      "Wide"       => Ok(Self::Wide),
+      "Door"       => Ok(Self::Door),
+      "Volatile"   => Ok(Self::Volatile),
+      "Rotor"      => Ok(Self::Rotor),
+      "Teleporter" => Ok(Self::Teleporter),
+      "Gate"       => Ok(Self::Gate),
+      "Ice"        => Ok(Self::Ice),
+      "Key"        => Ok(Self::Key),
+      "Lock"       => Ok(Self::Lock),
+      _            => Err("Failed to parse string to type `Kind`".to_string())
+    }
+  }
+}
+
+/// A character of a level, which is not a part, at (column, line)
+type UnknownCharacter = (char,(u16,u16));
+/// The bit of a connector and, if it is a special connector, the kind of its part
+type Connector        = (i32,Option<Kind>);
+/// The parts of a shape before a move and the positions they end up at
+type MoveTargets      = (Vec<Part>,Vec<(u16,u16)>);
+
+/// A wall, lock, part, Volatile, teleporter, gate or ice at `pos`, which belongs to exactly one shape.
+/// Teleporters, gates and ice are terrain, which lies beneath the parts instead of occupying their cell:
+/// Both teleporters of a pair share the shape -n for the pair n, gates share their shape by `GATE_SHAPE`, ice has `ICE_SHAPE`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Part { pub id: i32, pub shape: i32, pub connectors: i32, pub kind: Kind, pub pos: (u16,u16) }
+
+/// Outcome of a valid move
+#[derive(Debug, PartialEq, Eq)]
+pub enum Moved {
+  /// The shape moved and merged with every shape it connects to into `shape`
+  Merged { before: Vec<Part>, shape: i32 },
+  /// A shape got hit by a Volatile or opened its doors and was split into new shapes, or opened locks by its keys:
+  /// Every part of it and every lock opened with the part now at its position, if any, and the shape now at the target position of the move
+  Split { before: Vec<Part>, split: Vec<(Part,Option<Part>)>, selected: Option<i32> }
+}
+
+/// The position one `step` away from `pos`, if it is on the board
+#[inline]
+fn offset((x,y): (u16,u16), (dx,dy): (i32,i32)) -> Option<(u16,u16)> {
+  Some((u16::try_from(i32::from(x)+dx).ok()?, u16::try_from(i32::from(y)+dy).ok()?))
+}
+
+/// A board as a dense grid of part ids, with the ids of the parts of every shape.
+/// The open connectors by connector and shape, the parts with connectors of every shape and the doors, Volatiles and Rotors
+/// are counted along every change, so the completeness of the board is known without looking at every part.
+/// Every part changed by a move or rotation is kept as it was before, so only the changes need to be stored
+#[derive(Clone, Debug)]
+pub struct Board {
+  size            : (usize,usize),
+  cells           : Vec<Option<i32>>,
+  parts           : BTreeMap<i32,Part>,
+  shapes          : HashMap<i32,BTreeSet<i32>>,
+  next_shape      : i32,
+  terrain         : HashMap<(u16,u16),i32>,
+  open            : usize,
+  open_counts     : HashMap<Connector,usize>,
+  open_shapes     : HashMap<Connector,HashMap<i32,usize>>,
+  connected_shapes: HashMap<i32,usize>,
+  special         : usize,
+  changed         : BTreeMap<i32,Part>
+}
+
+impl Board {
+  pub fn new(parts: Vec<Part>) -> Self {
+    let mut board = Board {
+      size: (0,0), cells: Vec::new(), parts: BTreeMap::new(), shapes: HashMap::new(), next_shape: 1, terrain: HashMap::new(),
+      open: 0, open_counts: HashMap::new(), open_shapes: HashMap::new(), connected_shapes: HashMap::new(), special: 0, changed: BTreeMap::new()
+    };
+    for part in parts { board.insert(part) }
+    let open = board.parts.values().flat_map(|part| board.open_connectors_of(part).map(|(connector,_,_)| (part.shape, connector))).collect();
+    board.recount_open(Vec::new(), open);
+    board
+  }
+
+  /// Put `part` on the board and count it, without counting the connectors it opens or closes
+  fn insert(&mut self, part: Part) {
+    self.next_shape = max(self.next_shape, part.shape+1);
+    self.shapes.entry(part.shape).or_default().insert(part.id);
+    if part.connectors > 0 { *self.connected_shapes.entry(part.shape).or_default() += 1 }
+    if matches!(part.kind, Kind::Door | Kind::Volatile | Kind::Rotor) { self.special += 1 }
+    match part.kind {
+      Kind::Teleporter | Kind::Gate | Kind::Ice => { self.terrain.insert(part.pos, part.id); },
+      _                                         => self.set_cell(part.pos, Some(part.id))
+    }
+    self.parts.insert(part.id, part);
+  }
+
+  /// Put every part `id` back as it was or remove it, if it was not on the board, e.g. to undo or redo turns
+  #[cfg_attr(not(feature = "duckdb"), allow(dead_code))] // Only the history of the game restores turns
+  pub fn restore(&mut self, restored: Vec<(i32,Option<Part>)>) {
+    let positions = restored.iter()
+      .flat_map(|(id, part)| self.parts.get(id).map(|now| now.pos).into_iter().chain(part.as_ref().map(|part| part.pos)))
+      .collect::<Vec<(u16,u16)>>();
+    let open_then = self.open_near(&positions);
+    for (id, _) in restored.iter() { self.remove(*id) }
+    for part in restored.into_iter().filter_map(|(_, part)| part) { self.insert(part) }
+    self.recount_open(open_then, self.open_near(&positions));
+    self.changed.clear();
+  }
+
+  /// Parse the grid of a level into parts at (column, line) and form them into shapes,
+  /// or return every unknown character with its position
+  pub fn parse(grid_string: &str) -> Result<Self, Vec<UnknownCharacter>> {
+    let mut parts   = Vec::new();
+    let mut unknown = Vec::new();
+    for (y, line) in grid_string.lines().enumerate() {
+      for (x, c) in line.chars().enumerate().filter(|(_,c)| *c != ' ') {
+        let pos = (x as u16 + 1, y as u16 + 1);
+        match (Board::char_to_connectors(c), Board::char_to_kind(c)) {
+          (Some(connectors), kind) => {
+            let shape = Board::char_to_terrain_shape(c).unwrap_or(0);
+            parts.push(Part { id: parts.len() as i32 + 1, shape, connectors, kind, pos })
+          },
+          (None, _)                => unknown.push((c, pos))
+        }
+      }
+    }
+    if !unknown.is_empty() { return Err(unknown) }
+    let mut board = Board::new(parts);
+    board.split(0);
+    board.changed.clear();
+    Ok(board)
+  }
+
+  /// Map terrain character `c` onto the shape shared by its teleporter pair, by the gates pointing the same way or by all ice
+  fn char_to_terrain_shape(c: char) -> Option<i32> {
+    match c {
+      '①'..='⑨' => Some('①' as i32 - c as i32 - 1),
+      '↑'       => Some(GATE_SHAPE - 8),
+      '→'       => Some(GATE_SHAPE - 4),
+      '↓'       => Some(GATE_SHAPE - 2),
+      '←'       => Some(GATE_SHAPE - 1),
+      '░'       => Some(ICE_SHAPE),
+      _         => None
+    }
+  }
+
+  /// Map character `c` to the corresponding connector number
+  pub fn char_to_connectors(c: char) -> Option<i32> {
+    Some(match c {
+      '█' | '◊' =>   0,
+      '╴'       =>   1,
+      '╸'       =>  1 << 4,
+      '╷'       =>   2,
+      '╻'       =>  2 << 4,
+      '┐'       =>   3,
+      '┒' | '╖' => ( 2 << 4) + 1,
+      '┑' | '╕' => ( 1 << 4) + 2,
+      '┓' | '╗' =>  3 << 4,
+      '╶'       =>   4,
+      '╺'       =>  4 << 4,
+      '─'       =>   5,
+      '╼'       => ( 4 << 4) + 1,
+      '╾'       => ( 1 << 4) + 4,
+      '━' | '═' =>  5 << 4,
+      '┌'       =>   6,
+      '┍' | '╒' => ( 4 << 4) + 2,
+      '┎' | '╓' => ( 2 << 4) + 4,
+      '┏' | '╔' =>  6 << 4,
+      '┬'       =>   7,
+      '┮'       => ( 4 << 4) + 3,
+      '┭'       => ( 1 << 4) + 6,
+      '┲'       => ( 6 << 4) + 1,
+      '┱'       => ( 3 << 4) + 4,
+      '┰' | '╥' => ( 2 << 4) + 5,
+      '┯' | '╤' => ( 5 << 4) + 2,
+      '┳' | '╦' =>  7 << 4,
+      '╵'       =>   8,
+      '╹'       =>  8 << 4,
+      '┘'       =>   9,
+      '┚' | '╜' => ( 8 << 4) + 1,
+      '┙' | '╛' => ( 1 << 4) + 8,
+      '┛' | '╝' =>  9 << 4,
+      '│'       =>  10,
+      '╿'       => ( 8 << 4) + 2,
+      '╽'       => ( 2 << 4) + 8,
+      '┃' | '║' => 10 << 4,
+      '┤'       =>  11,
+      '┦'       => ( 8 << 4) + 3,
+      '┧'       => ( 2 << 4) + 9,
+      '┩'       => ( 9 << 4) + 2,
+      '┪'       => ( 3 << 4) + 8,
+      '┥' | '╡' => ( 1 << 4) + 10,
+      '┨' | '╢' => (10 << 4) + 1,
+      '┫' | '╣' => 11 << 4,
+      '└'       =>  12,
+      '┖' | '╙' => ( 8 << 4) + 4,
+      '┕' | '╘' => ( 4 << 4) + 8,
+      '┗' | '╚' => 12 << 4,
+      '┴'       =>  13,
+      '┶'       => ( 4 << 4) + 9,
+      '┵'       => ( 1 << 4) + 12,
+      '┺'       => (12 << 4) + 1,
+      '┹'       => ( 9 << 4) + 4,
+      '┸' | '╨' => ( 8 << 4) + 5,
+      '┷' | '╧' => ( 5 << 4) + 8,
+      '┻' | '╩' => 13 << 4,
+      '├'       =>  14,
+      '┞'       => ( 8 << 4) + 6,
+      '┟'       => ( 2 << 4) + 12,
+      '┡'       => (12 << 4) + 2,
+      '┢'       => ( 6 << 4) + 8,
+      '┝' | '╞' => ( 4 << 4) + 10,
+      '┠' | '╟' => (10 << 4) + 4,
+      '┣' | '╠' => 14 << 4,
+      '┼'       =>  15,
+      '╀'       => ( 8 << 4) + 7,
+      '╈'       => ( 7 << 4) + 8,
+      '┾'       => ( 4 << 4) + 11,
+      '╉'       => (11 << 4) + 4,
+      '╁'       => ( 2 << 4) + 13,
+      '╇'       => (13 << 4) + 2,
+      '┽'       => (14 << 4) + 1,
+      '╊'       => ( 1 << 4) + 14,
+      '╅'       => ( 3 << 4) + 12,
+      '╄'       => (12 << 4) + 3,
+      '╃'       => ( 9 << 4) + 6,
+      '╆'       => ( 6 << 4) + 9,
+      '╂' | '╫' => (10 << 4) + 5,
+      '┿' | '╪' => ( 5 << 4) + 10,
+      '╋' | '╬' => 15 << 4,
+      '◃'       =>   1,
+      '▿'       =>   2,
+      '╮'       =>   3,
+      '▹'       =>   4,
+      '╌'       =>   5,
+      '╭'       =>   6,
+      '▾'       =>   7,
+      '▵'       =>   8,
+      '╯'       =>   9,
+      '╎'       =>  10,
+      '◂'       =>  11,
+      '╰'       =>  12,
+      '▴'       =>  13,
+      '▸'       =>  14,
+      '╳'       =>  15,
+      '①'..='⑨' =>   0,
+      '↑'|'→'|'↓'|'←' =>   0,
+      '░'       =>   0,
+      '▓'       =>   0,
+      '⊣'       =>   1,
+      '⊤'       =>   2,
+      '⊢'       =>   4,
+      '⊥'       =>   8,
+      _         => return None
+    })
+  }
+
+  /// Map character `c` to its kind
+  pub fn char_to_kind(c: char) -> Kind {
+    match c {
+      '◊'                                                                                                     => Kind::Volatile,
+      '╸'|'╻'|'┒'|'┑'|'┓'|'╺'|'╼'|'╾'|'━'|'┍'|'┎'|'┏'|'┮'|'┰'|'┭'|'┲'|'┱'|'┯'|'┳'|'╹'|'┚'|
+      '┙'|'┛'|'╿'|'╽'|'┃'|'┦'|'┧'|'┥'|'┩'|'┨'|'┪'|'┫'|'┖'|'┕'|'┗'|'┸'|'┶'|'┵'|'┺'|'┷'|'┹'|
+      '┻'|'┞'|'┝'|'┟'|'┡'|'┢'|'┠'|'┣'|'╀'|'┾'|'╁'|'┽'|'╄'|'╂'|'╃'|'╆'|'┿'|'╅'|'╊'|'╇'|'╉'|'╈'|'╋'         => Kind::Wide,
+      '╖'|'╕'|'╗'|'═'|'╒'|'╓'|'╔'|'╥'|'╤'|'╦'|'╜'|'╛'|'╝'|'║'|'╢'|'╡'|'╣'|'╙'|'╘'|'╚'|'╨'|'╧'|'╩'|'╞'|'╟'|'╠'|'╫'|'╪'|'╬' => Kind::Door,
+      '◃'|'▿'|'╮'|'▹'|'╌'|'╭'|'▾'|'▵'|'╯'|'╎'|'◂'|'╰'|'▴'|'▸'|'╳'                                             => Kind::Rotor,
+      '①'..='⑨'                                                                                               => Kind::Teleporter,
+      '↑'|'→'|'↓'|'←'                                                                                         => Kind::Gate,
+      '░'                                                                                                     => Kind::Ice,
+      '▓'                                                                                                     => Kind::Lock,
+      '⊣'|'⊤'|'⊢'|'⊥'                                                                                         => Kind::Key,
+      _                                                                                                       => Kind::None
+    }
+  }
+
+  pub fn parts(&self) -> impl Iterator<Item=&Part> { self.parts.values() }
+  pub fn part_at(&self, pos: (u16,u16)) -> Option<&Part> { self.cell(pos).and_then(|id| self.parts.get(&id)) }
+  pub fn terrain_at(&self, pos: (u16,u16)) -> Option<&Part> { self.terrain.get(&pos).and_then(|id| self.parts.get(id)) }
+
+  /// All parts of `shape` ordered by id
+  pub fn shape_parts(&self, shape: i32) -> Vec<Part> {
+    self.shapes.get(&shape).map_or(Vec::new(), |ids| ids.iter().filter_map(|id| self.parts.get(id).cloned()).collect())
+  }
+
+  #[inline]
+  fn cell(&self, (x,y): (u16,u16)) -> Option<i32> {
+    let (x,y) = (usize::from(x), usize::from(y));
+    if x < self.size.0 && y < self.size.1 { self.cells[y*self.size.0+x] } else { None }
+  }
+
+  /// Put part `id` at `pos`, growing the grid to cover it
+  fn set_cell(&mut self, (x,y): (u16,u16), id: Option<i32>) {
+    let (x,y) = (usize::from(x), usize::from(y));
+    if x >= self.size.0 || y >= self.size.1 {
+      let (w,h)     = (max(self.size.0, x+1), max(self.size.1, y+1));
+      let mut cells = vec![None; w*h];
+      for row in 0..self.size.1 {
+        cells[row*w..row*w+self.size.0].copy_from_slice(&self.cells[row*self.size.0..(row+1)*self.size.0]);
+      }
+      (self.size, self.cells) = ((w,h), cells);
+    }
+    self.cells[y*self.size.0+x] = id;
+  }
+
+  /// Keep part `id` as it was before the current move or rotation, once it changes for the first time
+  fn touch(&mut self, id: i32) {
+    if let (Entry::Vacant(entry), Some(part)) = (self.changed.entry(id), self.parts.get(&id)) { entry.insert(part.clone()); }
+  }
+
+  /// Every part changed by the last move or rotation as it was before and as it is now, if it is still on the board
+  #[cfg_attr(not(feature = "duckdb"), allow(dead_code))] // Only the history of the game stores the changes
+  pub fn changes(&self) -> Vec<(Part,Option<Part>)> {
+    self.changed.values()
+      .map(|before| (before.clone(), self.parts.get(&before.id).cloned()))
+      .filter(|(before, now)| now.as_ref() != Some(before))
+      .collect()
+  }
+
+  fn remove(&mut self, id: i32) {
+    self.touch(id);
+    if let Some(part) = self.parts.remove(&id) {
+      if let Some(ids) = self.shapes.get_mut(&part.shape) {
+        ids.remove(&id);
+        if ids.is_empty() { self.shapes.remove(&part.shape); }
+      }
+      if part.connectors > 0 { self.uncount_connected(part.shape, 1) }
+      if matches!(part.kind, Kind::Door | Kind::Volatile | Kind::Rotor) { self.special -= 1 }
+      // A Volatile on top of another part does not occupy its cell
+      if self.cell(part.pos) == Some(id) { self.set_cell(part.pos, None) }
+      if self.terrain.get(&part.pos) == Some(&id) { self.terrain.remove(&part.pos); }
+    }
+  }
+
+  /// The position of the teleporter paired with the teleporter at `pos`, if there is a pair
+  fn exit(&self, pos: (u16,u16)) -> Option<(u16,u16)> {
+    let ids = self.shapes.get(&self.terrain_at(pos).filter(|part| part.kind == Kind::Teleporter)?.shape)?;
+    if ids.len() != 2 { return None }
+    ids.iter().map(|id| self.parts[id].pos).find(|&exit| exit != pos)
+  }
+
+  /// True, if a gate lies at `pos`, which does not let a part enter by `step`
+  fn is_gated(&self, pos: (u16,u16), step: (i32,i32)) -> bool {
+    self.terrain_at(pos).filter(|part| part.kind == Kind::Gate)
+      .is_some_and(|gate| !CONNECTOR_BITS[4..].iter().any(|&(bit,direction,_,_)| gate.shape == GATE_SHAPE - bit && direction == step))
+  }
+
+  /// True, if ice lies at `pos`
+  #[inline]
+  fn is_ice(&self, pos: (u16,u16)) -> bool { self.terrain_at(pos).is_some_and(|part| part.kind == Kind::Ice) }
+
+  /// The part one `step` away from `pos`, if any
+  #[inline]
+  fn neighbour(&self, pos: (u16,u16), step: (i32,i32)) -> Option<&Part> { offset(pos, step).and_then(|pos| self.part_at(pos)) }
+
+  /// True, if `part` and the `other` part are adjacent, a connector of `part` aligns with its counterpart of `other` and both have the same kind,
+  /// Rotors connect like parts without a kind
+  fn connects(part: &Part, other: &Part) -> bool {
+    let joined = |kind: Kind| if matches!(kind, Kind::Rotor | Kind::Key) { Kind::None } else { kind };
+    joined(part.kind) == joined(other.kind) && CONNECTOR_BITS.iter().any(|&(bit,step,counterpart,_)|
+      (part.connectors & bit) == bit && (other.connectors & counterpart) == counterpart && offset(part.pos, step) == Some(other.pos))
+  }
+
+  /// Every connector of `part` not aligned with its counterpart, special connectors only align with one of the same kind,
+  /// with its counterpart, both together with their kind, if special, and the position it points to
+  fn open_connectors_of<'a>(&'a self, part: &'a Part) -> impl Iterator<Item = (Connector,Connector,(i32,i32))> + 'a {
+    CONNECTOR_BITS.iter()
+      .filter(|&&(bit,step,counterpart,special)| (part.connectors & bit) == bit
+        && !self.neighbour(part.pos, step).is_some_and(|other| (other.connectors & counterpart) == counterpart && (!special || other.kind == part.kind)))
+      .map(|&(bit,(dx,dy),counterpart,special)| {
+        let kind = if special { Some(part.kind) } else { None };
+        ((bit,kind), (counterpart,kind), (i32::from(part.pos.0)+dx, i32::from(part.pos.1)+dy))
+      })
+  }
+
+  /// The counterpart of `connector` of the same kind
+  #[inline]
+  fn counterpart((bit,kind): Connector) -> Connector {
+    (CONNECTOR_BITS.iter().find(|&&(b,..)| b == bit).map_or(0, |&(_,_,counterpart,_)| counterpart), kind)
+  }
+
+  /// The open connectors with the shape of their part of the parts at `positions` and next to them,
+  /// the only ones that can open or close by changing the parts at `positions`
+  fn open_near(&self, positions: &[(u16,u16)]) -> Vec<(i32,Connector)> {
+    let ids: BTreeSet<i32> = positions.iter()
+      .flat_map(|&pos| std::iter::once(self.part_at(pos)).chain(CONNECTOR_BITS[4..].iter().map(move |&(_,step,_,_)| self.neighbour(pos, step))))
+      .flatten()
+      .map(|part| part.id)
+      .collect();
+    ids.iter().map(|id| &self.parts[id]).flat_map(|part| self.open_connectors_of(part).map(|(connector,_,_)| (part.shape, connector))).collect()
+  }
+
+  /// Replace the counted open connectors `then` by the open connectors `now`
+  fn recount_open(&mut self, then: Vec<(i32,Connector)>, now: Vec<(i32,Connector)>) {
+    for (shape, connector) in then {
+      self.open -= 1;
+      if let Some(count) = self.open_counts.get_mut(&connector) {
+        *count -= 1;
+        if *count == 0 { self.open_counts.remove(&connector); }
+      }
+      if let Some(shapes) = self.open_shapes.get_mut(&connector) {
+        if let Some(count) = shapes.get_mut(&shape) {
+          *count -= 1;
+          if *count == 0 { shapes.remove(&shape); }
+        }
+        if shapes.is_empty() { self.open_shapes.remove(&connector); }
+      }
+    }
+    for (shape, connector) in now {
+      self.open += 1;
+      *self.open_counts.entry(connector).or_default() += 1;
+      *self.open_shapes.entry(connector).or_default().entry(shape).or_default() += 1;
+    }
+  }
+
+  /// Take `count` parts with connectors from `shape`
+  fn uncount_connected(&mut self, shape: i32, count: usize) {
+    if let Some(connected) = self.connected_shapes.get_mut(&shape) {
+      *connected -= count;
+      if *connected == 0 { self.connected_shapes.remove(&shape); }
+    }
+  }
+
+  /// Give every group of connected parts of `shape` a new shape
+  fn split(&mut self, shape: i32) {
+    let Some(mut ids) = self.shapes.remove(&shape) else { return };
+    let open_then = ids.iter().flat_map(|id| self.open_connectors_of(&self.parts[id]).map(|(connector,_,_)| (shape, connector))).collect();
+    let mut open_now = Vec::new();
+    while let Some(first) = ids.pop_first() {
+      let mut members = BTreeSet::from([first]);
+      let mut queue   = VecDeque::from([first]);
+      while let Some(id) = queue.pop_front() {
+        let part = &self.parts[&id];
+        let connected: Vec<i32> = CONNECTOR_BITS.iter()
+          .filter_map(|&(_,step,_,_)| self.neighbour(part.pos, step))
+          .filter(|other| ids.contains(&other.id) && Board::connects(part, other))
+          .map(|other| other.id)
+          .collect();
+        for id in connected {
+          if ids.remove(&id) {
+            members.insert(id);
+            queue.push_back(id);
+          }
+        }
+      }
+      let new_shape = self.next_shape;
+      self.next_shape += 1;
+      for &id in members.iter() {
+        self.touch(id);
+        if let Some(part) = self.parts.get_mut(&id) { part.shape = new_shape }
+      }
+      let connected = members.iter().filter(|id| self.parts[id].connectors > 0).count();
+      self.uncount_connected(shape, connected);
+      if connected > 0 { *self.connected_shapes.entry(new_shape).or_default() += connected }
+      open_now.extend(members.iter().flat_map(|id| self.open_connectors_of(&self.parts[id]).map(|(connector,_,_)| (new_shape, connector))));
+      self.shapes.insert(new_shape, members);
+    }
+    self.recount_open(open_then, open_now);
+  }
+
+  /// Every part of `completed`, which got split, with the part now at its position and the shape now at `there`
+  fn split_result(&self, before: Vec<Part>, completed: Vec<Part>, shape: i32, there: (u16,u16)) -> Moved {
+    let mut selected = Some(shape);
+    let split = completed.into_iter().map(|part| {
+      let now = self.part_at(part.pos).cloned();
+      if part.pos == there { selected = now.as_ref().map(|now| now.shape) }
+      (part, now)
+    }).collect();
+    Moved::Split { before, split, selected }
+  }
+
+  /// Merge every shape `shape` connects to into `shape` and return true, if any merged
+  fn merge(&mut self, shape: i32) -> bool {
+    let mut merged = BTreeSet::new();
+    for id in self.shapes.get(&shape).into_iter().flatten() {
+      let part = &self.parts[id];
+      merged.extend(CONNECTOR_BITS.iter()
+        .filter_map(|&(_,step,_,_)| self.neighbour(part.pos, step))
+        .filter(|other| other.shape != shape && Board::connects(part, other))
+        .map(|other| other.shape));
+    }
+    for other in merged.iter() {
+      if let Some(ids) = self.shapes.remove(other) {
+        for &id in ids.iter() {
+          self.touch(id);
+          if let Some(part) = self.parts.get_mut(&id) { part.shape = shape }
+        }
+        self.shapes.entry(shape).or_default().extend(ids);
+        if let Some(connected) = self.connected_shapes.remove(other) { *self.connected_shapes.entry(shape).or_default() += connected }
+        for shapes in self.open_shapes.values_mut() {
+          if let Some(count) = shapes.remove(other) { *shapes.entry(shape).or_default() += count }
+        }
+      }
+    }
+    !merged.is_empty()
+  }
+
+  /// Open the doors of `shape` with its parts `completed` and split it, if it is complete and has any doors
+  fn open_doors(&mut self, shape: i32, completed: &[Part]) -> bool {
+    if completed.iter().any(|part| self.open_connectors_of(part).next().is_some()) || !completed.iter().any(|part| part.kind == Kind::Door) { return false }
+    let doors     = completed.iter().filter(|part| part.kind == Kind::Door).map(|part| part.pos).collect::<Vec<(u16,u16)>>();
+    let open_then = self.open_near(&doors);
+    for door in completed.iter().filter(|part| part.kind == Kind::Door) {
+      self.touch(door.id);
+      if (door.connectors & SPECIAL_CONNECTORS) > 0 && (door.connectors & NORMAL_CONNECTORS) == 0 {
+        self.remove(door.id);
+      } else if let Some(part) = self.parts.get_mut(&door.id) {
+        // Partial doors keep their normal connectors
+        part.connectors &= NORMAL_CONNECTORS;
+        part.kind        = Kind::None;
+        self.special    -= 1;
+      }
+    }
+    self.recount_open(open_then, self.open_near(&doors));
+    self.split(shape);
+    true
+  }
+
+  /// The parts of `shape` and the positions they end up at, when moving it by the step from `here` to `there` within `(w,h)`,
+  /// or `None`, if it cannot move
+  fn move_targets(&self, shape: i32, (here_x,here_y): (u16,u16), (there_x,there_y): (u16,u16), (w,h): (u16,u16)) -> Option<MoveTargets> {
+    let step   = (i32::from(there_x)-i32::from(here_x), i32::from(there_y)-i32::from(here_y));
+    let before = self.shape_parts(shape);
+    if before.is_empty() { return None }
+
+    // Collision detection
+    let is_volatile = before.iter().any(|part| part.kind == Kind::Volatile);
+    let shift       = |positions: &[(u16,u16)], step: (i32,i32)| positions.iter().map(|&pos| offset(pos, step).filter(|&(x,y)| x < w && y < h)).collect::<Option<Vec<(u16,u16)>>>();
+    let collides    = |targets: &[(u16,u16)]| targets.iter().any(|&pos|
+      (!is_volatile && self.part_at(pos).is_some_and(|other| other.shape != shape))
+        || (before.iter().all(|part| part.pos != pos) && self.is_gated(pos, step)));
+    let targets     = shift(&before.iter().map(|part| part.pos).collect::<Vec<(u16,u16)>>(), step)?;
+    if collides(&targets) { return None }
+
+    // Relocate the shape by the first teleporter any part lands on, which it did not cover before the step, if it fits at the exit
+    let teleport = |from: &[(u16,u16)], targets: Vec<(u16,u16)>| {
+      let landed = targets.iter().filter(|&pos| !from.contains(pos)).find_map(|&pos| self.exit(pos).map(|exit| (pos, exit)));
+      match landed {
+        Some(((x,y),(exit_x,exit_y))) => shift(&targets, (i32::from(exit_x)-i32::from(x), i32::from(exit_y)-i32::from(y))).filter(|exited| !collides(exited)),
+        None                          => Some(targets)
+      }
+    };
+    let mut targets = teleport(&before.iter().map(|part| part.pos).collect::<Vec<(u16,u16)>>(), targets)?;
+
+    // Slide across the ice and teleport at every step, until a Volatile hit a part or the next step would move onto another part,
+    // enter a gate the wrong way, leave the board or teleport to an exit the shape does not fit at
+    let hits     = |targets: &[(u16,u16)]| targets.iter().any(|&pos| self.part_at(pos).is_some_and(|other| other.shape != shape));
+    // Teleporters could send the shape around in circles
+    let mut slid = HashSet::from([targets.clone()]);
+    while !hits(&targets) && targets.iter().any(|&pos| self.is_ice(pos)) {
+      let Some(next) = shift(&targets, step).filter(|next| !next.iter().any(|&pos| !targets.contains(&pos) && self.is_gated(pos, step))) else { break };
+      if !is_volatile && hits(&next) { break }
+      match teleport(&targets, next) {
+        Some(next) if slid.insert(next.clone()) => targets = next,
+        _                                       => break
+      }
+    }
+    Some((before, targets))
+  }
+
+  /// Move `shape` by the step from `here` to `there`, if every part stays within `(w,h)`, enters gates only the way they point and,
+  /// unless it is a Volatile, moves onto no other shape.
+  /// Once a part lands on a teleporter, the shape is relocated, so this part is at the paired exit, if it fits there the same way.
+  /// While any part is on ice, the shape slides on by the same step and teleports like before, until the next step would not fit
+  /// or a Volatile hit a part. A Volatile removes everything at the position it moves onto and splits the shape it hit,
+  /// any other shape merges with every shape it connects to and, once merged, opens every lock next to its keys,
+  /// and every lock next to an opened one, and, once complete, opens its doors and splits.
+  pub fn move_shape(&mut self, shape: i32, here: (u16,u16), there: (u16,u16), bounds: (u16,u16)) -> Option<Moved> {
+    let (before, targets) = self.move_targets(shape, here, there, bounds)?;
+    self.changed.clear();
+
+    // Lift the shape off the grid and put it down at its targets
+    let changed   = before.iter().map(|part| part.pos).chain(targets.iter().copied()).collect::<Vec<(u16,u16)>>();
+    let open_then = self.open_near(&changed);
+    for part in before.iter() { self.set_cell(part.pos, None) }
+    let mut hit = None;
+    for (part, &pos) in before.iter().zip(targets.iter()) {
+      self.touch(part.id);
+      if let Some(moved) = self.parts.get_mut(&part.id) { moved.pos = pos }
+      match self.cell(pos) {
+        Some(other) => hit = Some(other),
+        None        => self.set_cell(pos, Some(part.id))
+      }
+    }
+
+    // Remove all parts at the position the Volatile moved onto
+    let hit = hit.map(|hit| {
+      let hit_part  = self.parts[&hit].clone();
+      let completed = self.shape_parts(hit_part.shape);
+      self.remove(hit);
+      for (part, &pos) in before.iter().zip(targets.iter()) {
+        if pos == hit_part.pos { self.remove(part.id) }
+      }
+      (hit_part, completed)
+    });
+    self.recount_open(open_then, self.open_near(&changed));
+
+    // Split the shape the Volatile hit
+    if let Some((hit_part, completed)) = hit {
+      self.split(hit_part.shape);
+      return Some(self.split_result(before, completed, hit_part.shape, there));
+    }
+
+    // Merge every shape the moved shape connects to and, if any merged, open the locks next to its keys,
+    // if the merged shape is complete and has any doors, open them
+    let merged    = self.merge(shape);
+    let locks     = if merged { self.open_locks(shape) } else { Vec::new() };
+    let completed = self.shape_parts(shape);
+    if (merged && self.open_doors(shape, &completed)) || !locks.is_empty() {
+      return Some(self.split_result(before, completed.into_iter().chain(locks).collect(), shape, there))
+    }
+    Some(Moved::Merged { before, shape })
+  }
+
+  /// Remove every lock next to a key of `shape` together with every lock next to a removed one and return the removed locks,
+  /// so a key opens a whole wall of locks it touches at once, as long as no other part interrupts it
+  fn open_locks(&mut self, shape: i32) -> Vec<Part> {
+    let mut locks = Vec::new();
+    let mut next  = self.shape_parts(shape).into_iter().filter(|part| part.kind == Kind::Key).map(|part| part.pos).collect::<Vec<(u16,u16)>>();
+    while let Some(pos) = next.pop() {
+      for &(_,step,_,_) in CONNECTOR_BITS[4..].iter() {
+        if let Some(lock) = self.neighbour(pos, step).filter(|part| part.kind == Kind::Lock).cloned() {
+          self.remove(lock.id);
+          next.push(lock.pos);
+          locks.push(lock);
+        }
+      }
+    }
+    locks
+  }
+
+  /// The connectors rotated clockwise by 90°, special connectors and normal connectors each among themselves
+  #[inline]
+  fn rotate_connectors(connectors: i32) -> i32 {
+    let rotate = |c: i32| ((c >> 1) | ((c & 1) << 3)) & NORMAL_CONNECTORS;
+    (rotate(connectors >> 4) << 4) | rotate(connectors & NORMAL_CONNECTORS)
+  }
+
+  /// Rotate every Rotor of `shape` clockwise by 90° with the cursor at `there`, if it has any.
+  /// The shape splits where it no longer connects, then the Rotors merge with every shape they connect to, open the locks next to its keys,
+  /// like a moved shape, and, once complete, open its doors. Every part of the shape and of the shapes next to its Rotors changes.
+  pub fn rotate_shape(&mut self, shape: i32, there: (u16,u16)) -> Option<Moved> {
+    let before = self.shape_parts(shape);
+    let rotors = before.iter().filter(|part| part.kind == Kind::Rotor).map(|part| part.id).collect::<Vec<i32>>();
+    if rotors.is_empty() { return None }
+    self.changed.clear();
+
+    // Keep the parts of the shape and of every shape, which the Rotors might merge with
+    let positions = rotors.iter().map(|id| self.parts[id].pos).collect::<Vec<(u16,u16)>>();
+    let neighbours: BTreeSet<i32> = positions.iter()
+      .flat_map(|&pos| CONNECTOR_BITS[4..].iter().filter_map(move |&(_,step,_,_)| offset(pos, step)))
+      .filter_map(|pos| self.part_at(pos).map(|part| part.shape))
+      .filter(|&other| other != shape)
+      .collect();
+    let completed = before.iter().cloned().chain(neighbours.into_iter().flat_map(|other| self.shape_parts(other))).collect::<Vec<Part>>();
+
+    let open_then = self.open_near(&positions);
+    for &id in rotors.iter() {
+      self.touch(id);
+      if let Some(part) = self.parts.get_mut(&id) { part.connectors = Board::rotate_connectors(part.connectors) }
+    }
+    self.recount_open(open_then, self.open_near(&positions));
+
+    self.split(shape);
+    let mut locks = Vec::new();
+    for id in rotors.iter() {
+      let rotor_shape = self.parts[id].shape;
+      if self.merge(rotor_shape) {
+        locks.extend(self.open_locks(rotor_shape));
+        let merged = self.shape_parts(rotor_shape);
+        self.open_doors(rotor_shape, &merged);
+      }
+    }
+    Some(self.split_result(before, completed.into_iter().chain(locks).collect(), shape, there))
+  }
+
+  /// True, if neither a complete nor a partially complete board can be reached anymore
+  /// Without Volatiles and Rotors, connectors only ever close in pairs and shapes only split, if doors open
+  fn is_unsolvable(&self) -> bool {
+    if self.special > 0 || self.connected_shapes.len() <= 1 { return false }
+    let has_partner = |shape: i32, connector: Connector| self.open_shapes.get(&Board::counterpart(connector))
+      .is_some_and(|others| others.len() > 1 || !others.contains_key(&shape));
+    // An open connector has no open counterpart in another shape it could ever connect to
+    let unmatched  = self.open_shapes.iter().any(|(&connector, shapes)| shapes.keys().any(|&shape| !has_partner(shape, connector)));
+    // The number of open connectors differs from the number of their open counterparts
+    let unbalanced = self.open_counts.iter().any(|(&connector, count)| self.open_counts.get(&Board::counterpart(connector)) != Some(count));
+    // A shape has no open connector with an open counterpart in another shape, so it can never merge
+    let isolated   = self.connected_shapes.keys().any(|&shape| !self.open_shapes.iter().any(|(&connector, shapes)| shapes.contains_key(&shape) && has_partner(shape, connector)));
+    (unmatched || unbalanced) && isolated
+  }
+
+  /// An estimate of the turns left to complete the board: for every open connector the distance between the position it points to
+  /// and the nearest open counterpart of another shape, one turn can close at most two of them
+  pub fn remaining(&self) -> usize {
+    // The shape and the position pointed to of every open connector, grouped by the connector, so each only meets its counterparts
+    let mut open = HashMap::new();
+    for part in self.parts.values() {
+      for (connector, _, target) in self.open_connectors_of(part) { open.entry(connector).or_insert_with(Vec::new).push((part.shape, target)) }
+    }
+    let distances: usize = open.iter()
+      .flat_map(|(&connector, pointing)| {
+        let counterparts = open.get(&Board::counterpart(connector)).map_or(&[][..], Vec::as_slice);
+        // The counterpart points back to the part of this connector
+        let (_,(dx,dy),_,_) = CONNECTOR_BITS.iter().find(|&&(bit,..)| bit == Board::counterpart(connector).0).copied().unwrap_or_default();
+        pointing.iter().map(move |&(shape, (x,y))| counterparts.iter()
+          .filter(|&&(other, _)| other != shape)
+          .map(|&(_, (other_x,other_y))| (other_x-dx-x).unsigned_abs() as usize + (other_y-dy-y).unsigned_abs() as usize)
+          .min()
+          .unwrap_or(1))
+      })
+      .sum();
+    distances.div_ceil(2) + self.connected_shapes.len().saturating_sub(1)
+  }
+
+  /// The completeness from the counted open connectors and shapes with connectors,
+  /// only a board with several shapes and neither doors nor Volatiles is searched for being unsolvable
+  pub fn completeness(&self) -> Completeness {
+    if !self.connected_shapes.is_empty() && self.open == 0 {
+      Completeness::Complete
+    } else if self.connected_shapes.len() == 1 {
+      Completeness::PartiallyComplete
+    } else if self.is_unsolvable() {
+      Completeness::Unsolvable
+    } else {
+      Completeness::Incomplete
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape_at(board: &Board, pos: (u16,u16)) -> i32 { board.part_at(pos).map_or(0, |part| part.shape) }
+
+    #[test]
+    fn parse_into_shapes() {
+      let board = Board::parse("█████\n█┌╶ █\n█└─╴█\n█████").expect("Level could not be parsed");
+      assert_eq!(board.parts().count(), 19);
+      assert_eq!(shape_at(&board, (2,2)), shape_at(&board, (4,3)));
+      assert_ne!(shape_at(&board, (2,2)), shape_at(&board, (3,2)));
+      assert_ne!(shape_at(&board, (1,1)), shape_at(&board, (2,1)));
+      assert_eq!(Board::parse("███\n█x█\n███").err(), Some(vec![('x',(2,2))]));
+    }
+
+    #[test]
+    /// █████    █████
+    /// █╶ ╴█ -> █ ╶╴█
+    /// █████    █████
+    fn move_and_merge() {
+      let mut board = Board::parse("█████\n█╶ ╴█\n█████").expect("Level could not be parsed");
+      let shape     = shape_at(&board, (2,2));
+      assert_eq!(board.move_shape(shape, (2,2), (2,1), (5,3)), None);
+      assert_eq!(board.completeness(), Completeness::Incomplete);
+      assert!(matches!(board.move_shape(shape, (2,2), (3,2), (5,3)), Some(Moved::Merged { shape: merged, .. }) if merged == shape));
+      assert_eq!(shape_at(&board, (4,2)), shape);
+      assert_eq!(board.part_at((2,2)), None);
+      assert_eq!(board.completeness(), Completeness::Complete);
+    }
+
+    #[test]
+    /// █████    █████
+    /// █╶ ╴█ -> █ ╶╴█
+    /// █████    █████
+    fn changes_of_move() {
+      let mut board = Board::parse("█████\n█╶ ╴█\n█████").expect("Level could not be parsed");
+      let (moved, merged) = (board.part_at((2,2)).cloned().expect("No part"), board.part_at((4,2)).cloned().expect("No part"));
+      assert!(board.changes().is_empty());
+      assert!(board.move_shape(moved.shape, (2,2), (3,2), (5,3)).is_some());
+      assert_eq!(board.changes(), vec![
+        (moved.clone(),  Some(Part { pos: (3,2), ..moved.clone() })),
+        (merged.clone(), Some(Part { shape: moved.shape, ..merged }))
+      ]);
+      assert_eq!(board.move_shape(moved.shape, (3,2), (3,1), (5,3)), None);
+      assert_eq!(board.changes().len(), 2);
+    }
+
+    #[test]
+    /// █████    █████
+    /// █╔╗ █ -> █   █
+    /// █╚ ╝█    █   █
+    /// █████    █████
+    fn open_doors() {
+      let mut board = Board::parse("█████\n█╔╗ █\n█╚ ╝█\n█████").expect("Level could not be parsed");
+      let shape     = shape_at(&board, (4,3));
+      let Some(Moved::Split { split, selected, .. }) = board.move_shape(shape, (4,3), (3,3), (5,4)) else { panic!("Doors did not open") };
+      assert_eq!(split.len(), 4);
+      assert!(split.iter().all(|(_,now)| now.is_none()));
+      assert_eq!(selected, None);
+      assert_eq!(board.parts().filter(|part| part.connectors > 0).count(), 0);
+    }
+
+    #[test]
+    /// █████    █████
+    /// █ ◊ █ -> █   █
+    /// █╶┬╴█    █╶ ╴█
+    /// █████    █████
+    fn volatile_splits_shape() {
+      let mut board = Board::parse("█████\n█ ◊ █\n█╶┬╴█\n█████").expect("Level could not be parsed");
+      let volatile  = shape_at(&board, (3,2));
+      assert_eq!(shape_at(&board, (2,3)), shape_at(&board, (4,3)));
+      let Some(Moved::Split { split, selected, .. }) = board.move_shape(volatile, (3,2), (3,3), (5,4)) else { panic!("Volatile did not hit") };
+      assert_eq!(split.len(), 3);
+      assert_eq!(selected, None);
+      assert_eq!(board.part_at((3,2)), None);
+      assert_eq!(board.part_at((3,3)), None);
+      assert_ne!(shape_at(&board, (2,3)), shape_at(&board, (4,3)));
+    }
+
+    #[test]
+    /// █████    █████    █████    █████
+    /// █ ◊ █    █ ◊ █    █   █    █   █
+    /// █╶┬╴█ -> █╶┬╴█ -> █╶ ╴█ -> █╶╴ █
+    /// █╔╗ █    █   █    █   █    █   █
+    /// █╚ ╝█    █   █    █   █    █   █
+    /// █████    █████    █████    █████
+    fn counts_follow_moves() {
+      let mut board  = Board::parse("█████\n█ ◊ █\n█╶┬╴█\n█╔╗ █\n█╚ ╝█\n█████").expect("Level could not be parsed");
+      let recounted  = |board: &Board| Board::new(board.parts().cloned().collect());
+      let moves      = [((4,5),(3,5)), ((3,2),(3,3)), ((4,3),(3,3))];
+      for (here, there) in moves {
+        assert!(board.move_shape(shape_at(&board, here), here, there, (5,6)).is_some());
+        let fresh = recounted(&board);
+        assert_eq!(board.open, fresh.open);
+        assert_eq!(board.connected_shapes.len(), fresh.connected_shapes.len());
+        assert_eq!(board.special, fresh.special);
+        assert_eq!(board.completeness(), fresh.completeness());
+      }
+      assert_eq!(board.completeness(), Completeness::Complete);
+    }
+
+    #[test]
+    /// ██████    ██████    ██████
+    /// █╶╎╴█ -> █╶╌╴█ -> █╶╎╴█
+    /// ██████    ██████    ██████
+    fn rotate_and_merge() {
+      let mut board = Board::parse("██████\n█╶╎╴█\n██████").expect("Level could not be parsed");
+      assert_eq!(board.part_at((3,2)).map(|part| part.kind), Some(Kind::Rotor));
+      assert_ne!(shape_at(&board, (2,2)), shape_at(&board, (3,2)));
+      assert_eq!(board.rotate_shape(shape_at(&board, (2,2)), (2,2)), None);
+      let Some(Moved::Split { selected, .. }) = board.rotate_shape(shape_at(&board, (3,2)), (3,2)) else { panic!("Rotor did not rotate") };
+      assert_eq!(board.part_at((3,2)).map(|part| part.connectors), Some(0b0101));
+      assert_eq!(selected, Some(shape_at(&board, (2,2))));
+      assert_eq!(shape_at(&board, (2,2)), shape_at(&board, (4,2)));
+      assert_eq!(board.completeness(), Completeness::Complete);
+      assert!(board.rotate_shape(shape_at(&board, (3,2)), (3,2)).is_some());
+      assert_eq!(board.part_at((3,2)).map(|part| part.connectors), Some(0b1010));
+      assert_ne!(shape_at(&board, (2,2)), shape_at(&board, (3,2)));
+      assert_ne!(shape_at(&board, (3,2)), shape_at(&board, (4,2)));
+      assert_eq!(board.completeness(), Completeness::Incomplete);
+    }
+
+    #[test]
+    /// ███████    ███████    ███████
+    /// █╶ ┬ ╴█ -> █ ╶┬ ╴█ -> █  ╶┬╴█
+    /// █  ╵  █    █  ╵  █    █   ╵ █
+    /// ███████    ███████    ███████
+    fn open_connectors_counted_along_changes() {
+      let counted = |board: &Board| (board.open, board.open_counts.clone(), board.open_shapes.clone());
+      let mut board = Board::parse("███████\n█╶ ┬ ╴█\n█  ╵  █\n███████").expect("Level could not be parsed");
+      let moved     = shape_at(&board, (2,2));
+      assert!(board.move_shape(moved, (2,2), (3,2), (7,4)).is_some());
+      assert_eq!(counted(&board), counted(&Board::new(board.parts().cloned().collect())));
+      assert_eq!(board.completeness(), Completeness::Incomplete);
+      let merged    = shape_at(&board, (3,2));
+      assert!(board.move_shape(merged, (3,2), (4,2), (7,4)).is_some());
+      assert_eq!(counted(&board), counted(&Board::new(board.parts().cloned().collect())));
+      assert_eq!(board.open, 0);
+      assert_eq!(board.completeness(), Completeness::Complete);
+    }
+
+    #[test]
+    fn remaining_turns_estimated() {
+      let remaining = |level: &str| Board::parse(level).expect("Level could not be parsed").remaining();
+      assert_eq!(remaining("████\n█╶╴█\n████"), 0);
+      assert!(remaining("█████\n█╶ ╴█\n█████") < remaining("██████\n█╶  ╴█\n██████"));
+    }
+
+    #[test]
+    fn completeness_of_boards() {
+      let completeness = |level: &str| Board::parse(level).expect("Level could not be parsed").completeness();
+      assert_eq!(completeness("████\n█╶╴█\n████"),          Completeness::Complete);
+      assert_eq!(completeness("█████\n█╶─ █\n█████"),       Completeness::PartiallyComplete);
+      assert_eq!(completeness("█████\n█╶ ╴█\n█████"),       Completeness::Incomplete);
+      assert_eq!(completeness("████\n█╶╶█\n████"),          Completeness::Unsolvable);
+      assert_eq!(completeness("█████\n█╶╶◊█\n█████"),       Completeness::Incomplete);
+    }
+
+    #[test]
+    /// █████████    █████████
+    /// █╶① █ ①╴█ -> █ ① █ ╶╴█
+    /// █████████    █████████
+    /// █████████    █████████
+    /// █╷① █ ① █ -> █╷① █ ① █
+    /// █╵  █ █ █    █╵  █ █ █
+    /// █████████    █████████
+    fn teleport_to_exit() {
+      let mut board = Board::parse("█████████\n█╶① █ ①╴█\n█████████").expect("Level could not be parsed");
+      let shape     = shape_at(&board, (2,2));
+      assert_eq!(board.parts().filter(|part| part.kind == Kind::Teleporter).map(|part| part.shape).collect::<Vec<i32>>(), vec![-1, -1]);
+      assert!(board.move_shape(shape, (2,2), (3,2), (9,3)).is_some());
+      assert_eq!(board.part_at((3,2)), None);
+      assert_eq!(shape_at(&board, (7,2)), shape_at(&board, (8,2)));
+      assert_eq!(board.completeness(), Completeness::Complete);
+
+      let mut board = Board::parse("█████████\n█╷① █ ① █\n█╵  █ █ █\n█████████").expect("Level could not be parsed");
+      let shape     = shape_at(&board, (2,2));
+      assert_eq!(board.move_shape(shape, (2,2), (3,2), (9,4)), None);
+      assert_eq!(shape_at(&board, (2,3)), shape);
+    }
+
+    #[test]
+    /// █████    █████
+    /// █╶→╴█ -> █ ╶╴█
+    /// █████    █████
+    fn gate_lets_pass_one_way() {
+      let mut board = Board::parse("█████\n█╶→╴█\n█████").expect("Level could not be parsed");
+      assert_eq!(board.parts().filter(|part| part.kind == Kind::Gate).map(|part| part.shape).collect::<Vec<i32>>(), vec![GATE_SHAPE - 4]);
+      assert_eq!(board.move_shape(shape_at(&board, (4,2)), (4,2), (3,2), (5,3)), None);
+      assert!(board.move_shape(shape_at(&board, (2,2)), (2,2), (3,2), (5,3)).is_some());
+      assert_eq!(shape_at(&board, (3,2)), shape_at(&board, (4,2)));
+      assert_eq!(board.completeness(), Completeness::Complete);
+    }
+
+    #[test]
+    /// ████████    ████████
+    /// █╶░░░ ╴█ -> █ ░░░╶╴█
+    /// ████████    ████████
+    /// ███████     ███████
+    /// █╶░░░╴█  -> █ ░░╶╴█
+    /// ███████     ███████
+    fn slide_across_ice() {
+      let mut board = Board::parse("████████\n█╶░░░ ╴█\n████████").expect("Level could not be parsed");
+      assert!(board.move_shape(shape_at(&board, (2,2)), (2,2), (3,2), (8,3)).is_some());
+      assert_eq!(board.part_at((3,2)), None);
+      assert_eq!(shape_at(&board, (6,2)), shape_at(&board, (7,2)));
+      assert_eq!(board.completeness(), Completeness::Complete);
+
+      let mut board = Board::parse("███████\n█╶░░░╴█\n███████").expect("Level could not be parsed");
+      assert!(board.move_shape(shape_at(&board, (2,2)), (2,2), (3,2), (7,3)).is_some());
+      assert_eq!(shape_at(&board, (5,2)), shape_at(&board, (6,2)));
+      assert_eq!(board.completeness(), Completeness::Complete);
+    }
+
+    #[test]
+    /// █████    █████
+    /// █⊣ ▓█ -> █ ⊣▓█
+    /// █████    █████
+    /// ██████    ██████
+    /// █╶╎⊣▓█ -> █╶╌⊣ █
+    /// ██████    ██████
+    fn key_opens_locks_once_merged() {
+      let mut board = Board::parse("█████\n█⊣ ▓█\n█████").expect("Level could not be parsed");
+      assert!(matches!(board.move_shape(shape_at(&board, (2,2)), (2,2), (3,2), (5,3)), Some(Moved::Merged { .. })));
+      assert_eq!(board.part_at((4,2)).map(|part| part.kind), Some(Kind::Lock));
+
+      let mut board = Board::parse("██████\n█╶╎⊣▓█\n██████").expect("Level could not be parsed");
+      let Some(Moved::Split { split, .. }) = board.rotate_shape(shape_at(&board, (3,2)), (3,2)) else { panic!("Rotor did not rotate") };
+      assert!(split.iter().any(|(part,now)| part.pos == (5,2) && now.is_none()));
+      assert_eq!(board.part_at((5,2)), None);
+      assert_eq!(shape_at(&board, (2,2)), shape_at(&board, (4,2)));
+      assert_eq!(board.completeness(), Completeness::Complete);
+    }
+
+    #[test]
+    /// ████████    ████████
+    /// █╶─ ⊣▓▓█ -> █ ╶─⊣  █
+    /// ███▓██▓█    ███▓██ █
+    /// ████████    ████████
+    fn key_opens_adjacent_locks() {
+      let mut board = Board::parse("████████\n█╶─ ⊣▓▓█\n███▓██▓█\n████████").expect("Level could not be parsed");
+      let shape     = shape_at(&board, (2,2));
+      let Some(Moved::Split { split, .. }) = board.move_shape(shape, (2,2), (3,2), (8,4)) else { panic!("Locks did not open") };
+      assert_eq!(split.iter().filter(|(_,now)| now.is_none()).map(|(part,_)| part.pos).collect::<BTreeSet<(u16,u16)>>(), BTreeSet::from([(6,2),(7,2),(7,3)]));
+      assert_eq!(board.part_at((4,3)).map(|part| part.kind), Some(Kind::Lock));
+      assert_eq!(board.completeness(), Completeness::Complete);
+    }
+
+    #[test]
+    /// ████████    ████████
+    /// █◊░░─╴ █ -> █ ░░ ╴ █
+    /// ████████    ████████
+    fn volatile_slides_into_part() {
+      let mut board = Board::parse("████████\n█◊░░─╴ █\n████████").expect("Level could not be parsed");
+      let volatile  = shape_at(&board, (2,2));
+      let Some(Moved::Split { split, .. }) = board.move_shape(volatile, (2,2), (3,2), (8,3)) else { panic!("Volatile did not hit") };
+      assert!(split.iter().any(|(part,now)| part.pos == (5,2) && now.is_none()));
+      assert_eq!(board.part_at((2,2)), None);
+      assert_eq!(board.part_at((4,2)), None);
+      assert_eq!(board.part_at((5,2)), None);
+      assert_eq!(board.part_at((6,2)).map(|part| part.connectors), Some(0b0001));
+    }
+
+    #[test]
+    /// ████████    ████████
+    /// █╶░①█①╴█ -> █ ░①█╶╴█
+    /// ████████    ████████
+    fn slide_into_teleporter() {
+      let mut board = Board::parse("████████\n█╶░①█①╴█\n████████").expect("Level could not be parsed");
+      assert!(board.move_shape(shape_at(&board, (2,2)), (2,2), (3,2), (8,3)).is_some());
+      assert_eq!(board.part_at((4,2)), None);
+      assert_eq!(shape_at(&board, (6,2)), shape_at(&board, (7,2)));
+      assert_eq!(board.completeness(), Completeness::Complete);
+    }
+
+    #[test]
+    /// ███████    ███████
+    /// █╶─ ⊣▓█ -> █ ╶─⊣ █
+    /// ███▓███    ███▓███
+    fn key_opens_locks() {
+      let mut board = Board::parse("███████\n█╶─ ⊣▓█\n███▓███").expect("Level could not be parsed");
+      let shape     = shape_at(&board, (2,2));
+      assert_eq!(board.part_at((5,2)).map(|part| part.kind), Some(Kind::Key));
+      assert_ne!(shape, shape_at(&board, (5,2)));
+      assert_eq!(board.move_shape(shape_at(&board, (5,2)), (5,2), (6,2), (7,3)), None);
+      let Some(Moved::Split { split, selected, .. }) = board.move_shape(shape, (2,2), (3,2), (7,3)) else { panic!("Locks did not open") };
+      assert_eq!(split.iter().filter(|(_,now)| now.is_none()).map(|(part,_)| part.pos).collect::<Vec<(u16,u16)>>(), vec![(6,2)]);
+      assert_eq!(selected, Some(shape));
+      assert_eq!(shape_at(&board, (3,2)), shape_at(&board, (5,2)));
+      assert_eq!(board.part_at((6,2)), None);
+      assert_eq!(board.part_at((4,3)).map(|part| part.kind), Some(Kind::Lock));
+      assert_eq!(board.completeness(), Completeness::Complete);
+    }
+}
@@ -0,0 +1,378 @@
+use std::{cmp::Reverse, collections::{hash_map::Entry, BTreeMap, BTreeSet, BinaryHeap, HashMap, VecDeque}, fmt};
+
+use super::{Board, Completeness, Direction, Kind, CONNECTOR_BITS};
+
+pub const DEFAULT_SEARCH_LIMIT: usize = 250_000;
+/// How much the estimated turns left outweigh the turns taken, trading the shortest solution for a quicker search
+const WEIGHT              : usize = 3;
+/// How many explored boards are kept to replay the moves to the next board from
+const RECENT_BOARDS       : usize = 1024;
+const SHAPE_DIRECTIONS    : [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action { Step(Direction), Rotate }
+
+/// Move the shape with an object at `pos` one step into a direction or rotate its Rotors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move { pub pos: (u16,u16), pub action: Action }
+
+impl fmt::Display for Move {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.action {
+      Action::Step(direction) => write!(f, "Move shape at ({},{}) {:?}", self.pos.0, self.pos.1, direction),
+      Action::Rotate          => write!(f, "Rotate shape at ({},{})", self.pos.0, self.pos.1)
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Solution { pub moves: Vec<Move>, pub completeness: Completeness }
+
+/// Why a search ended: a complete board was found, the first board is known to be unsolvable,
+/// every reachable board has been explored or the limit of boards to explore was reached
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchEnd { Complete, Unsolvable, Exhausted, LimitReached }
+
+/// Outcome of a search: the best solution found, the number of explored boards and why the search ended
+#[derive(Debug, PartialEq, Eq)]
+pub struct Search { pub solution: Option<Solution>, pub explored: usize, pub end: SearchEnd }
+
+impl Search {
+  /// The lines describing the outcome of the search for level `level_path`, the moves of a solution included
+  pub fn report(&self, level_path: &str) -> Vec<String> {
+    let explored = self.explored;
+    let reason   = match self.end {
+      SearchEnd::Complete     => format!("{} boards explored", explored),
+      SearchEnd::Unsolvable   => "its open connectors can never be matched".to_string(),
+      SearchEnd::Exhausted    => format!("all {} reachable boards explored", explored),
+      SearchEnd::LimitReached => format!("search limit of {} explored boards reached", explored)
+    };
+    match &self.solution {
+      Some(solution) => std::iter::once(format!("Level `{}` is {} after {} turns ({}):", level_path, solution.completeness, solution.moves.len(), reason))
+        .chain(solution.moves.iter().enumerate().map(|(turn, shape_move)| format!("{:>5}: {}", turn+1, shape_move)))
+        .collect(),
+      None if self.end == SearchEnd::LimitReached => vec![format!("No solution found for level `{}` ({})", level_path, reason)],
+      None                                        => vec![format!("Level `{}` cannot be solved ({})", level_path, reason)]
+    }
+  }
+}
+
+/// A board reached during the search, how it was reached from its parent board and after how many turns
+struct Node { parent: Option<(usize,Move)>, turns: usize }
+
+/// The position one step from `pos` into `direction` within `(w,h)`, if any
+fn step((x,y): (u16,u16), direction: Direction, (w,h): (u16,u16)) -> Option<(u16,u16)> {
+  match direction {
+    Direction::Up    => Some((x, y.checked_sub(1)?)),
+    Direction::Right => Some((x.checked_add(1).filter(|&x| x < w)?, y)),
+    Direction::Down  => Some((x, y.checked_add(1).filter(|&y| y < h)?)),
+    Direction::Left  => Some((x.checked_sub(1)?, y)),
+    _                => None
+  }
+}
+
+/// Identify a board by the connectors, kind and shape membership of the part in every cell,
+/// regardless of the shape ids assigned while moving, mixed into a hash to keep the visited boards small and quick to compare
+fn board_key(board: &Board) -> u64 {
+  let mut shapes: Vec<i32> = Vec::new();
+  board.cells.iter().enumerate()
+    .filter_map(|(index, id)| Some((index, &board.parts[&(*id)?])))
+    .fold(0, |key: u64, (index, part)| {
+      let shape = shapes.iter().position(|&s| s == part.shape).unwrap_or_else(|| { shapes.push(part.shape); shapes.len()-1 });
+      let value = (index as u64) << 32 | (part.connectors as u64) << 16 | (part.kind as u64) << 8 | shape as u64;
+      (key.rotate_left(5) ^ value).wrapping_mul(0x517c_c1b7_2722_0a95)
+    })
+}
+
+/// Apply `shape_move` to `board` within `bounds`, if the shape it names can make it
+fn apply(board: &mut Board, shape_move: Move, bounds: (u16,u16)) -> Option<()> {
+  let shape = board.part_at(shape_move.pos)?.shape;
+  match shape_move.action {
+    Action::Step(direction) => board.move_shape(shape, shape_move.pos, step(shape_move.pos, direction, bounds)?, bounds).map(|_| ()),
+    Action::Rotate          => board.rotate_shape(shape, shape_move.pos).map(|_| ())
+  }
+}
+
+/// All boards reachable from `board` by moving a single shape one step within `bounds` or rotating its Rotors
+fn next_boards(board: &Board, bounds: (u16,u16)) -> Vec<(Move,Board,Completeness)> {
+  // Every movable shape with the position of its first part
+  let mut shapes = BTreeMap::new();
+  for part in board.parts().filter(|part| part.connectors > 0 || part.kind == Kind::Volatile) {
+    shapes.entry(part.shape).or_insert(part.pos);
+  }
+  let rotatable: BTreeSet<i32> = board.parts().filter(|part| part.kind == Kind::Rotor).map(|part| part.shape).collect();
+  let mut boards = Vec::new();
+  for (shape, here) in shapes {
+    for direction in SHAPE_DIRECTIONS {
+      if let Some(there) = step(here, direction, bounds) {
+        // Only copy the board for moves the shape can make
+        if board.move_targets(shape, here, there, bounds).is_none() { continue }
+        let mut next = board.clone();
+        if next.move_shape(shape, here, there, bounds).is_some() {
+          let completeness = next.completeness();
+          boards.push((Move { pos: here, action: Action::Step(direction) }, next, completeness));
+        }
+      }
+    }
+    if rotatable.contains(&shape) {
+      let mut next = board.clone();
+      if next.rotate_shape(shape, here).is_some() {
+        let completeness = next.completeness();
+        boards.push((Move { pos: here, action: Action::Rotate }, next, completeness));
+      }
+    }
+  }
+  boards
+}
+
+/// True, if a connector faces a part of its own shape without its counterpart, which only a split could ever free
+fn is_blocked(board: &Board) -> bool {
+  board.special == 0 && board.parts().any(|part| CONNECTOR_BITS.iter().any(|&(bit,step,_,_)| (part.connectors & bit) == bit
+    && board.neighbour(part.pos, step).is_some_and(|other| other.shape == part.shape && !Board::connects(part, other))))
+}
+
+/// The moves leading from the first board to the board of node `index`
+fn moves_to(nodes: &[Node], mut index: usize) -> Vec<Move> {
+  let mut moves = Vec::new();
+  while let Some((parent, shape_move)) = nodes[index].parent {
+    moves.push(shape_move);
+    index = parent;
+  }
+  moves.reverse();
+  moves
+}
+
+/// Best-first search over shape moves starting at `board`, preferring boards with few turns taken and few estimated turns left,
+/// until a complete board is found or `limit` boards have been explored. If no complete board is found, fall back to the first partially complete one.
+pub fn search(board: &Board, limit: usize) -> Search { Searcher::new(board).resume(limit) }
+
+/// A search, which can be resumed to explore more boards, see `search`.
+/// Only the moves are kept for every board found, the board to explore next is replayed from the first board.
+pub struct Searcher {
+  board       : Board,
+  bounds      : (u16,u16),
+  nodes       : Vec<Node>,
+  visited     : HashMap<u64,usize>,
+  queue       : BinaryHeap<(Reverse<usize>,Reverse<usize>)>,
+  recent      : HashMap<usize,Board>,
+  recent_order: VecDeque<usize>,
+  explored    : usize,
+  partial     : Option<usize>,
+  complete    : Option<usize>,
+  /// The explored board with the fewest estimated turns left, if fewer than for the first board
+  closest     : Option<(usize,usize)>,
+  unsolvable  : bool
+}
+
+impl Searcher {
+  pub fn new(board: &Board) -> Self {
+    let remaining = board.remaining();
+    let mut searcher = Searcher {
+      board       : board.clone(),
+      bounds      : board.parts().fold((0,0), |(w,h), part| (w.max(part.pos.0+1), h.max(part.pos.1+1))),
+      nodes       : vec![Node { parent: None, turns: 0 }],
+      visited     : HashMap::from([(board_key(board), 0)]),
+      queue       : BinaryHeap::from([(Reverse(WEIGHT*remaining), Reverse(0))]),
+      recent      : HashMap::new(),
+      recent_order: VecDeque::new(),
+      explored    : 0,
+      partial     : None,
+      complete    : None,
+      closest     : None,
+      unsolvable  : false
+    };
+    match board.completeness() {
+      Completeness::Complete          => searcher.complete   = Some(0),
+      Completeness::PartiallyComplete => searcher.partial    = Some(0),
+      Completeness::Incomplete        => (),
+      Completeness::Unsolvable        => searcher.unsolvable = true
+    }
+    searcher
+  }
+
+  /// Explore up to `limit` more boards, unless a complete board has been found already, and return the outcome of the search so far
+  pub fn resume(&mut self, limit: usize) -> Search {
+    let priority = |board: &Board, turns: usize| Reverse(turns + WEIGHT*board.remaining());
+    let first_remaining = self.board.remaining();
+    let mut explored = 0;
+    while explored < limit && self.complete.is_none() && !self.unsolvable {
+      let Some((_, Reverse(index))) = self.queue.pop() else { break };
+      // Replay the moves from the closest recently explored board
+      let mut moves    = Vec::new();
+      let mut ancestor = index;
+      while !self.recent.contains_key(&ancestor) {
+        let Some((parent, shape_move)) = self.nodes[ancestor].parent else { break };
+        moves.push(shape_move);
+        ancestor = parent;
+      }
+      let mut current = self.recent.get(&ancestor).unwrap_or(&self.board).clone();
+      for &shape_move in moves.iter().rev() { apply(&mut current, shape_move, self.bounds); }
+      explored += 1;
+      let turns = self.nodes[index].turns + 1;
+      for (shape_move, next, completeness) in next_boards(&current, self.bounds) {
+        if let Entry::Vacant(entry) = self.visited.entry(board_key(&next)) {
+          self.nodes.push(Node { parent: Some((index, shape_move)), turns });
+          let next_index = self.nodes.len()-1;
+          entry.insert(next_index);
+          match completeness {
+            Completeness::Complete          => { self.complete = Some(next_index); break },
+            Completeness::PartiallyComplete => { self.partial = self.partial.or(Some(next_index)) },
+            Completeness::Incomplete        => (),
+            // Do not explore boards that can never be solved
+            Completeness::Unsolvable        => continue
+          }
+          if is_blocked(&next) { continue }
+          let remaining = next.remaining();
+          if remaining < self.closest.map_or(first_remaining, |(closest, _)| closest) { self.closest = Some((remaining, next_index)) }
+          self.queue.push((priority(&next, turns), Reverse(next_index)));
+        }
+      }
+      if self.recent_order.len() == RECENT_BOARDS { self.recent_order.pop_front().map(|oldest| self.recent.remove(&oldest)); }
+      self.recent_order.push_back(index);
+      self.recent.insert(index, current);
+    }
+    self.explored += explored;
+    Search {
+      solution : self.complete.map(|index| Solution { moves: moves_to(&self.nodes, index), completeness: Completeness::Complete })
+                   .or_else(|| self.partial.map(|index| Solution { moves: moves_to(&self.nodes, index), completeness: Completeness::PartiallyComplete })),
+      explored : self.explored,
+      end      : if self.unsolvable { SearchEnd::Unsolvable }
+                 else if self.complete.is_some() { SearchEnd::Complete }
+                 else if self.queue.is_empty() { SearchEnd::Exhausted }
+                 else { SearchEnd::LimitReached }
+    }
+  }
+
+  /// True, if `board` is the first board of the search
+  pub fn starts_at(&self, board: &Board) -> bool { board_key(board) == board_key(&self.board) }
+
+  /// True, if `board` is the first board of the search or lies on the way to the best board found so far,
+  /// so resuming the search can still lead to a move from it
+  pub fn continues_from(&self, board: &Board) -> bool {
+    self.starts_at(board) || self.visited.get(&board_key(board)).is_some_and(|&node| self.moves_from(node).is_some())
+  }
+
+  /// The next move from `board` on the way to the best board found so far: a complete, a partially complete
+  /// or else the board with the fewest estimated turns left, while boards are left to explore, if `board` lies on the way to it
+  pub fn next_move(&self, board: &Board) -> Option<Move> {
+    self.moves_from(*self.visited.get(&board_key(board))?)?.first().copied()
+  }
+
+  /// The moves from the board of node `from` to the best board found so far, if it lies on the way to it
+  fn moves_from(&self, from: usize) -> Option<Vec<Move>> {
+    // The closest board only leads somewhere, while there are boards left to explore
+    let closest   = self.closest.filter(|_| !self.queue.is_empty()).map(|(_, index)| index);
+    let mut index = self.complete.or(self.partial).or(closest)?;
+    let mut moves = Vec::new();
+    while index != from {
+      let (parent, shape_move) = self.nodes[index].parent?;
+      moves.push(shape_move);
+      index = parent;
+    }
+    moves.reverse();
+    Some(moves)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// █████
+    /// █╶ ╴█
+    /// █┌┐ █
+    /// █└┘ █
+    /// █████
+    fn solve_two_shapes() {
+      let board    = Board::parse("█████\n█╶ ╴█\n█┌┐ █\n█└┘ █\n█████").expect("Level could not be parsed");
+      let search   = search(&board, DEFAULT_SEARCH_LIMIT);
+      let solution = search.solution.expect("No solution found, where one was expected");
+      assert_eq!(solution.moves.len(), 1);
+      assert_eq!(solution.completeness, Completeness::Complete);
+    }
+
+    #[test]
+    /// ██████
+    /// █╶╎╴█
+    /// ██████
+    fn solve_by_rotation() {
+      let board    = Board::parse("██████\n█╶╎╴█\n██████").expect("Level could not be parsed");
+      let search   = search(&board, DEFAULT_SEARCH_LIMIT);
+      let solution = search.solution.expect("No solution found, where one was expected");
+      assert_eq!(solution.moves, vec![Move { pos: (3,2), action: Action::Rotate }]);
+      assert_eq!(solution.completeness, Completeness::Complete);
+    }
+
+    #[test]
+    /// ████
+    /// █╶╶█
+    /// ████
+    fn search_unsolvable() {
+      let board  = Board::parse("████\n█╶╶█\n████").expect("Level could not be parsed");
+      let search = search(&board, DEFAULT_SEARCH_LIMIT);
+      assert_eq!(search.solution, None);
+      assert_eq!(search.end, SearchEnd::Unsolvable);
+      assert_eq!(search.report("test.lvl"), vec!["Level `test.lvl` cannot be solved (its open connectors can never be matched)".to_string()]);
+    }
+
+    #[test]
+    /// ███████
+    /// █╶ █ ╴█
+    /// ███████
+    fn search_exhausted() {
+      let board  = Board::parse("███████\n█╶ █ ╴█\n███████").expect("Level could not be parsed");
+      let search = search(&board, DEFAULT_SEARCH_LIMIT);
+      assert_eq!(search.solution, None);
+      assert_eq!(search.end, SearchEnd::Exhausted);
+    }
+
+    #[test]
+    /// █████
+    /// █╶ ╴█
+    /// █┌┐ █
+    /// █└┘ █
+    /// █████
+    fn search_limit_reached() {
+      let board  = Board::parse("█████\n█╶ ╴█\n█┌┐ █\n█└┘ █\n█████").expect("Level could not be parsed");
+      let search = search(&board, 0);
+      assert_eq!(search.solution, None);
+      assert_eq!(search.end, SearchEnd::LimitReached);
+      assert_eq!(search.report("test.lvl"), vec!["No solution found for level `test.lvl` (search limit of 0 explored boards reached)".to_string()]);
+    }
+
+    #[test]
+    /// █████
+    /// █╶ ╴█
+    /// █┌┐ █
+    /// █└┘ █
+    /// █████
+    fn searcher_resumes() {
+      let board        = Board::parse("█████\n█╶ ╴█\n█┌┐ █\n█└┘ █\n█████").expect("Level could not be parsed");
+      let mut searcher = Searcher::new(&board);
+      assert_eq!(searcher.resume(0).end, SearchEnd::LimitReached);
+      let search = searcher.resume(DEFAULT_SEARCH_LIMIT);
+      assert_eq!(search.end, SearchEnd::Complete);
+      assert!(search.explored > 0);
+      assert_eq!(searcher.next_move(&board), search.solution.and_then(|solution| solution.moves.first().copied()));
+      assert_eq!(searcher.resume(DEFAULT_SEARCH_LIMIT).explored, search.explored); // Nothing left to explore
+    }
+
+    #[test]
+    /// ██████
+    /// █╶  ╴█
+    /// ██████
+    fn searcher_continues_on_the_way() {
+      let board        = Board::parse("██████\n█╶  ╴█\n██████").expect("Level could not be parsed");
+      let mut searcher = Searcher::new(&board);
+      let moves        = searcher.resume(DEFAULT_SEARCH_LIMIT).solution.expect("No solution found, where one was expected").moves;
+      assert_eq!(moves.len(), 2);
+      let mut next = board.clone();
+      apply(&mut next, moves[0], searcher.bounds).expect("First move could not be made");
+      assert!(searcher.continues_from(&next));
+      assert_eq!(searcher.next_move(&next), Some(moves[1]));
+      // A board off the way is not continued from
+      let (_, other, _) = next_boards(&board, searcher.bounds).into_iter().find(|(shape_move, _, _)| *shape_move != moves[0]).expect("No other move found");
+      assert!(!searcher.continues_from(&other));
+    }
+}
@@ -4,5 +4,8 @@ extern crate chrono;
 
 mod conductor;
 mod common;
+mod engine;
 
-fn main() { if let Err(e) = conductor::run() {println!("{:?}", e); } }
+use std::process::ExitCode;
+
+fn main() -> ExitCode { conductor::run().unwrap_or_else(|e| { println!("{:?}", e); ExitCode::FAILURE }) }